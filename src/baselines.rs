@@ -0,0 +1,162 @@
+// scripted, non-neural brains to hold evolved ones up against. they see exactly what a
+// SumFxModel sees and neither learn nor inherit anything, so their generation stats are what
+// chance and a few fixed rules get out of a world
+
+use std::f32::consts::PI;
+
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    brain::{Brain, InputWidths, Observation},
+    consts::*,
+};
+
+// every output but movement says: build nothing, say nothing
+fn idle_outputs(forward: f32, rotate: f32) -> [f32; B_OUTPUT_LEN] {
+    let mut out = [0.; B_OUTPUT_LEN];
+    out[0] = forward;
+    out[1] = rotate;
+    out[2] = -1.;
+    out[3] = -1.;
+    out
+}
+
+// uniformly random outputs every tick. carries its own rng, seeded from the world's, so runs
+// stay reproducible and a snapshot resumes mid-stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RandomBrain {
+    rng: WorldRng,
+}
+
+impl Brain for RandomBrain {
+    type State = RandomBrain;
+
+    fn random(rng: &mut WorldRng, _widths: InputWidths) -> Self {
+        RandomBrain {
+            rng: WorldRng::seed_from_u64(rng.gen()),
+        }
+    }
+
+    fn act(&mut self, _obs: &Observation) -> [f32; B_OUTPUT_LEN] {
+        let mut out = [0.; B_OUTPUT_LEN];
+        out.iter_mut()
+            .for_each(|o| *o = self.rng.gen_range(-1.0..=1.0));
+        out
+    }
+
+    fn reset(&mut self) {}
+
+    fn crossover(self, _other: Self, _weight: f32) -> Self {
+        self
+    }
+
+    // a child must not replay its parent's draws
+    fn mutate(self, _rate: f32, rng: &mut WorldRng) -> Self {
+        RandomBrain {
+            rng: WorldRng::seed_from_u64(rng.gen()),
+        }
+    }
+
+    fn to_state(&self) -> Self::State {
+        self.clone()
+    }
+
+    fn from_state(state: &Self::State, _widths: InputWidths) -> Result<Self, String> {
+        Ok(state.clone())
+    }
+}
+
+// heads for the closest food in sight, wanders in a wide circle when there is none. reads the
+// food rows as check_collisions writes them with obs_version 2: 1 first, then distance, then the
+// sine and cosine of the bearing from its heading. it turns by that bearing over PI, as a rotate
+// output of 1 turns PI a tick. obs_version 1 rows only have the angle between the being's and the
+// food's positions as seen from the world's origin, which says nothing of where the food is from
+// the being, so it refuses them
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct GreedyForager;
+
+impl Brain for GreedyForager {
+    type State = GreedyForager;
+
+    fn random(_rng: &mut WorldRng, _widths: InputWidths) -> Self {
+        GreedyForager
+    }
+
+    fn check_widths(widths: InputWidths) -> Result<(), String> {
+        if widths.food_obstruct < 8 {
+            return Err("the greedy forager steers by egocentric rows, set obs_version = 2".into());
+        }
+        Ok(())
+    }
+
+    fn act(&mut self, obs: &Observation) -> [f32; B_OUTPUT_LEN] {
+        let nearest = obs
+            .food_obstructs
+            .iter()
+            .filter(|row| row[0] == 1.)
+            .min_by(|a, b| a[1].total_cmp(&b[1]));
+
+        match nearest {
+            Some(food) => idle_outputs(1., food[2].atan2(food[3]) / PI),
+            None => idle_outputs(1., 0.05),
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn crossover(self, _other: Self, _weight: f32) -> Self {
+        self
+    }
+
+    fn mutate(self, _rate: f32, _rng: &mut WorldRng) -> Self {
+        self
+    }
+
+    fn to_state(&self) -> Self::State {
+        *self
+    }
+
+    fn from_state(state: &Self::State, _widths: InputWidths) -> Result<Self, String> {
+        Ok(*state)
+    }
+}
+
+// backs away slowly on a curve, dropping an obstruct in front of itself every tick while it
+// has more than half its starting energy left
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct WallBuilder;
+
+impl Brain for WallBuilder {
+    type State = WallBuilder;
+
+    fn random(_rng: &mut WorldRng, _widths: InputWidths) -> Self {
+        WallBuilder
+    }
+
+    fn act(&mut self, obs: &Observation) -> [f32; B_OUTPUT_LEN] {
+        let mut out = idle_outputs(-0.5, 0.02);
+        if obs.own[4] > 0.5 {
+            out[2] = 1.;
+        }
+        out
+    }
+
+    fn reset(&mut self) {}
+
+    fn crossover(self, _other: Self, _weight: f32) -> Self {
+        self
+    }
+
+    fn mutate(self, _rate: f32, _rng: &mut WorldRng) -> Self {
+        self
+    }
+
+    fn to_state(&self) -> Self::State {
+        *self
+    }
+
+    fn from_state(state: &Self::State, _widths: InputWidths) -> Result<Self, String> {
+        Ok(*state)
+    }
+}
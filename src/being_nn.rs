@@ -2,24 +2,30 @@ use std::iter::zip;
 
 use burn::nn::Linear;
 use burn::prelude::*;
-use nn::{LinearConfig, Lstm, LstmConfig};
+use nn::{Lstm, LstmConfig};
 
 use burn::module::{Module, Param};
 use burn::nn::Relu;
 use burn::tensor::backend::Backend;
 use burn::tensor::{activation, Tensor};
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
 
-use crate::{B_OUTPUT_LEN, GENOME_LEN, SPEECHLET_LEN};
+use crate::{
+    brain::{Brain, InputWidths, Observation},
+    consts::*,
+};
 
 pub fn tensorize_2dvec<B: Backend>(
-    vec: &Vec<Vec<f32>>,
+    vec: &[Vec<f32>],
     shape: [usize; 2],
     device: &Device<B>,
 ) -> Tensor<B, 2> {
     Tensor::<B, 1>::from_floats(
-        vec.clone()
-            .into_iter()
+        vec.iter()
             .flatten()
+            .copied()
             .collect::<Vec<f32>>()
             .as_slice(),
         device,
@@ -51,7 +57,8 @@ impl Sigmoid {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "ActivationName", from = "ActivationName")]
 pub enum Activation {
     Relu(Relu),
     Tanh(Tanh),
@@ -59,6 +66,38 @@ pub enum Activation {
     Identity,
 }
 
+// how an Activation is named in snapshot and brain files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivationName {
+    Relu,
+    Tanh,
+    Sigmoid,
+    Identity,
+}
+
+impl From<Activation> for ActivationName {
+    fn from(act: Activation) -> Self {
+        match act {
+            Activation::Relu(_) => ActivationName::Relu,
+            Activation::Tanh(_) => ActivationName::Tanh,
+            Activation::Sigmoid(_) => ActivationName::Sigmoid,
+            Activation::Identity => ActivationName::Identity,
+        }
+    }
+}
+
+impl From<ActivationName> for Activation {
+    fn from(name: ActivationName) -> Self {
+        match name {
+            ActivationName::Relu => Activation::Relu(Relu::new()),
+            ActivationName::Tanh => Activation::Tanh(Tanh::new()),
+            ActivationName::Sigmoid => Activation::Sigmoid(Sigmoid::new()),
+            ActivationName::Identity => Activation::Identity,
+        }
+    }
+}
+
 trait Forward {
     fn forward<B: Backend, const D: usize>(&self, input: Tensor<B, D>) -> Tensor<B, D>;
 }
@@ -82,21 +121,17 @@ pub fn combine_linears<B: Backend>(
     right_weight: f32,
 ) -> Linear<B> {
     assert!(
-        lin1.weight.shape() == lin1.weight.shape(),
+        lin1.weight.shape() == lin2.weight.shape(),
         "linear constructs do not match"
     );
 
     let weight =
         lin1.weight.val().mul_scalar(left_weight) + lin2.weight.val().mul_scalar(right_weight);
-    let bias = {
-        if lin1.bias.is_none() {
-            None
-        } else {
-            Some(
-                lin1.bias.unwrap().val().mul_scalar(left_weight)
-                    + lin2.bias.unwrap().val().mul_scalar(right_weight),
-            )
+    let bias = match (lin1.bias, lin2.bias) {
+        (Some(b1), Some(b2)) => {
+            Some(b1.val().mul_scalar(left_weight) + b2.val().mul_scalar(right_weight))
         }
+        _ => None,
     };
     let (weight, bias) = (
         Param::from_tensor(weight),
@@ -106,6 +141,122 @@ pub fn combine_linears<B: Backend>(
     Linear { weight, bias }
 }
 
+// weights are drawn from the caller's rng rather than burn's global initializer so a seeded world
+// reproduces. the distributions follow burn's defaults: kaiming uniform for linears, xavier normal in lstms
+#[derive(Debug, Clone, Copy)]
+pub enum Init {
+    KaimingUniform,
+    XavierNormal,
+}
+
+fn random_values(n: usize, fan_in: usize, fan_out: usize, init: Init, rng: &mut impl Rng) -> Vec<f32> {
+    match init {
+        Init::KaimingUniform => {
+            let a = 1. / (fan_in as f32).sqrt();
+            (0..n).map(|_| rng.gen_range(-a..a)).collect()
+        }
+        Init::XavierNormal => {
+            let std = (2. / (fan_in + fan_out) as f32).sqrt();
+            (0..n)
+                .map(|_| rng.sample::<f32, _>(StandardNormal) * std)
+                .collect()
+        }
+    }
+}
+
+pub fn random_linear<B: Backend>(
+    d_input: usize,
+    d_output: usize,
+    init: Init,
+    rng: &mut impl Rng,
+    device: &Device<B>,
+) -> Linear<B> {
+    let weight = random_values(d_input * d_output, d_input, d_output, init, rng);
+    let bias = random_values(d_output, d_input, d_output, init, rng);
+
+    Linear {
+        weight: Param::from_tensor(
+            Tensor::<B, 1>::from_floats(weight.as_slice(), device).reshape([d_input, d_output]),
+        ),
+        bias: Some(Param::from_tensor(Tensor::<B, 1>::from_floats(
+            bias.as_slice(),
+            device,
+        ))),
+    }
+    .no_grad()
+}
+
+pub fn random_lstm<B: Backend>(
+    d_input: usize,
+    d_hidden: usize,
+    rng: &mut impl Rng,
+    device: &Device<B>,
+) -> Lstm<B> {
+    let gates = (0..4)
+        .map(|_| {
+            (
+                random_linear(d_input, d_hidden, Init::XavierNormal, rng, device),
+                random_linear(d_hidden, d_hidden, Init::XavierNormal, rng, device),
+            )
+        })
+        .collect();
+
+    lstm_from_gates(d_input, d_hidden, gates, device)
+}
+
+// gates as (input_transform, hidden_transform) in input, forget, output, cell order
+fn lstm_gates<B: Backend>(lstm: &Lstm<B>) -> Vec<(Linear<B>, Linear<B>)> {
+    let record = lstm.clone().into_record();
+    [
+        record.input_gate,
+        record.forget_gate,
+        record.output_gate,
+        record.cell_gate,
+    ]
+    .into_iter()
+    .map(|gate| {
+        let (i, h) = (gate.input_transform, gate.hidden_transform);
+        (
+            Linear {
+                weight: i.weight,
+                bias: i.bias,
+            },
+            Linear {
+                weight: h.weight,
+                bias: h.bias,
+            },
+        )
+    })
+    .collect()
+}
+
+// gates as (input_transform, hidden_transform) in input, forget, output, cell order
+fn lstm_from_gates<B: Backend>(
+    d_input: usize,
+    d_hidden: usize,
+    gates: Vec<(Linear<B>, Linear<B>)>,
+    device: &Device<B>,
+) -> Lstm<B> {
+    // burn only builds the module skeleton here, every parameter is then overwritten
+    let lstm = LstmConfig::new(d_input, d_hidden, true).init(device);
+    let mut record = lstm.clone().into_record();
+
+    for (gate, (input_transform, hidden_transform)) in zip(
+        [
+            &mut record.input_gate,
+            &mut record.forget_gate,
+            &mut record.output_gate,
+            &mut record.cell_gate,
+        ],
+        gates,
+    ) {
+        gate.input_transform = input_transform.into_record();
+        gate.hidden_transform = hidden_transform.into_record();
+    }
+
+    lstm.load_record(record).no_grad()
+}
+
 #[derive(Debug, Clone)]
 pub struct FF<B: Backend> {
     pub lins: Vec<Linear<B>>,
@@ -115,6 +266,7 @@ pub struct FF<B: Backend> {
 pub fn create_ff<B: Backend>(
     layer_sizes: Vec<usize>,
     activations: Vec<Activation>,
+    rng: &mut impl Rng,
     device: &Device<B>,
 ) -> FF<B> {
     assert!(
@@ -127,11 +279,14 @@ pub fn create_ff<B: Backend>(
     );
     FF {
         lins: (0..layer_sizes.len() - 1)
-            .into_iter()
             .map(|i| {
-                LinearConfig::new(layer_sizes[i], layer_sizes[i + 1])
-                    .init(device)
-                    .no_grad()
+                random_linear(
+                    layer_sizes[i],
+                    layer_sizes[i + 1],
+                    Init::KaimingUniform,
+                    rng,
+                    device,
+                )
             })
             .collect(),
         acts: activations,
@@ -145,11 +300,37 @@ impl<B: Backend> FF<B> {
             x = act.forward(x);
         }
 
-        return x;
+        x
+    }
+
+    // a child layer by layer, weight being how much of self it takes after
+    pub fn crossover(self, other: FF<B>, weight: f32) -> FF<B> {
+        FF {
+            lins: zip(self.lins, other.lins)
+                .map(|(a, b)| combine_linears(a, b, weight, 1. - weight).no_grad())
+                .collect(),
+            acts: self.acts,
+        }
+    }
+
+    pub fn mutate(self, rate: f32, rng: &mut impl Rng, device: &Device<B>) -> FF<B> {
+        FF {
+            lins: self
+                .lins
+                .into_iter()
+                .map(|lin| {
+                    let [inp_size, outp_size] = lin.weight.shape().dims;
+                    let mutation_lin =
+                        random_linear(inp_size, outp_size, Init::KaimingUniform, rng, device);
+                    combine_linears(lin, mutation_lin, 1., rate)
+                })
+                .collect(),
+            acts: self.acts,
+        }
     }
 }
 
-fn combine_lstms<B: Backend>(
+pub(crate) fn combine_lstms<B: Backend>(
     lstm_1: Lstm<B>,
     lstm_2: Lstm<B>,
     left_weight: f32,
@@ -221,6 +402,7 @@ pub struct SumFxModel<B: Backend> {
 }
 
 impl<B: Backend> SumFxModel<B> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         being_config: (Vec<usize>, Vec<Activation>),
         fo_config: (Vec<usize>, Vec<Activation>),
@@ -230,11 +412,12 @@ impl<B: Backend> SumFxModel<B> {
 
         concat_before_final: bool,
 
+        rng: &mut impl Rng,
         device: &Device<B>,
     ) -> Self {
         let lstm_inp_size = {
             if !concat_before_final {
-                being_config.0.last().unwrap().clone()
+                *being_config.0.last().unwrap()
             } else {
                 being_config.0.last().unwrap()
                     + fo_config.0.last().unwrap()
@@ -243,9 +426,7 @@ impl<B: Backend> SumFxModel<B> {
             }
         };
 
-        let intermediate_dim: usize;
-
-        if !concat_before_final {
+        let intermediate_dim = if !concat_before_final {
             assert!(
                 being_config.0.last() == fo_config.0.last()
                     && being_config.0.last() == speechlet_config.0.last()
@@ -256,31 +437,29 @@ impl<B: Backend> SumFxModel<B> {
                 final_config.0.first() == being_config.0.last(),
                 "sensory model output and final model input must be the same size, since you chose mean mode"
             );
-            intermediate_dim = being_config.0.last().unwrap().clone();
+            *being_config.0.last().unwrap()
         } else {
             assert!(
                 &(being_config.0.last().unwrap() + fo_config.0.last().unwrap() + speechlet_config.0.last().unwrap() + self_config.0.last().unwrap()) == final_config.0.first().unwrap(),
                 "sensory model output sizes must add up to final model input size, since you chose concat mode"
             );
-            intermediate_dim = being_config.0.last().unwrap()
+            being_config.0.last().unwrap()
                 + fo_config.0.last().unwrap()
                 + speechlet_config.0.last().unwrap()
-                + self_config.0.last().unwrap();
-        }
+                + self_config.0.last().unwrap()
+        };
 
         SumFxModel {
-            being_model: create_ff::<B>(being_config.0, being_config.1, device),
-            fo_model: create_ff::<B>(fo_config.0, fo_config.1, device),
-            speechlet_model: create_ff::<B>(speechlet_config.0, speechlet_config.1, device),
-            self_model: create_ff::<B>(self_config.0, self_config.1, device),
-            lstm: LstmConfig::new(lstm_inp_size, lstm_inp_size, true)
-                .init(device)
-                .no_grad(),
-            final_model: create_ff(final_config.0, final_config.1, device),
+            being_model: create_ff::<B>(being_config.0, being_config.1, rng, device),
+            fo_model: create_ff::<B>(fo_config.0, fo_config.1, rng, device),
+            speechlet_model: create_ff::<B>(speechlet_config.0, speechlet_config.1, rng, device),
+            self_model: create_ff::<B>(self_config.0, self_config.1, rng, device),
+            lstm: random_lstm(lstm_inp_size, lstm_inp_size, rng, device),
+            final_model: create_ff(final_config.0, final_config.1, rng, device),
 
-            concat_before_final: concat_before_final,
-            intermediate_dim: intermediate_dim,
-            lstm_inp_size: lstm_inp_size,
+            concat_before_final,
+            intermediate_dim,
+            lstm_inp_size,
             state: (
                 Tensor::<B, 2>::zeros([1, intermediate_dim], device).no_grad(),
                 Tensor::<B, 2>::zeros([1, intermediate_dim], device).no_grad(),
@@ -288,17 +467,17 @@ impl<B: Backend> SumFxModel<B> {
         }
     }
 
-    pub fn standard_model(device: &Device<B>) -> Self {
+    pub fn standard_model(widths: InputWidths, rng: &mut impl Rng, device: &Device<B>) -> Self {
         let being_config = (
-            vec![3 + GENOME_LEN, 8],
+            vec![widths.being, 8],
             vec![Activation::Tanh(Tanh {}), Activation::Tanh(Tanh {})],
         );
         let fo_config = (
-            vec![5, 8],
+            vec![widths.food_obstruct, 8],
             vec![Activation::Tanh(Tanh {}), Activation::Tanh(Tanh {})],
         );
         let speechlet_config = (
-            vec![SPEECHLET_LEN, 8],
+            vec![widths.speechlet, 8],
             vec![Activation::Tanh(Tanh {}), Activation::Tanh(Tanh {})],
         );
         let self_config = (
@@ -309,15 +488,16 @@ impl<B: Backend> SumFxModel<B> {
             vec![32, B_OUTPUT_LEN],
             vec![Activation::Tanh(Tanh {}), Activation::Tanh(Tanh {})],
         );
-        return SumFxModel::new(
+        SumFxModel::new(
             being_config,
             fo_config,
             speechlet_config,
             self_config,
             final_config,
             true,
+            rng,
             device,
-        );
+        )
     }
 
     pub fn forward(
@@ -353,9 +533,7 @@ impl<B: Backend> SumFxModel<B> {
         self.state = (c.clone(), h.clone());
 
         let final_output = self.final_model.forward(h).squeeze(0);
-        let final_output = activation::tanh(final_output);
-
-        final_output
+        activation::tanh(final_output)
     }
 
     pub fn crossover(
@@ -364,9 +542,7 @@ impl<B: Backend> SumFxModel<B> {
         crossover_weight: f32,
         device: &Device<B>,
     ) -> SumFxModel<B> {
-        let mut new_models: Vec<FF<B>> = vec![];
-
-        for (self_model, other_model) in zip(
+        let new_models: Vec<FF<B>> = zip(
             [
                 self.being_model,
                 self.fo_model,
@@ -381,24 +557,11 @@ impl<B: Backend> SumFxModel<B> {
                 other.self_model,
                 other.final_model,
             ],
-        ) {
-            let mut newlins: Vec<Linear<B>> = vec![];
-
-            for (self_lin, other_lin) in zip(self_model.lins, other_model.lins) {
-                let newlin =
-                    combine_linears(self_lin, other_lin, crossover_weight, 1. - crossover_weight)
-                        .no_grad();
-                newlins.push(newlin);
-            }
-
-            let new_model = FF {
-                lins: newlins,
-                acts: self_model.acts.clone(),
-            };
-            new_models.push(new_model);
-        }
+        )
+        .map(|(a, b)| a.crossover(b, crossover_weight))
+        .collect();
 
-        return SumFxModel {
+        SumFxModel {
             being_model: new_models[0].to_owned(),
             fo_model: new_models[1].to_owned(),
             speechlet_model: new_models[2].to_owned(),
@@ -415,41 +578,31 @@ impl<B: Backend> SumFxModel<B> {
             intermediate_dim: self.intermediate_dim,
             lstm_inp_size: self.lstm_inp_size,
             state: (
-                Tensor::<B, 2>::zeros([1, self.intermediate_dim as usize], device),
-                Tensor::<B, 2>::zeros([1, self.intermediate_dim as usize], device),
+                Tensor::<B, 2>::zeros([1, self.intermediate_dim], device),
+                Tensor::<B, 2>::zeros([1, self.intermediate_dim], device),
             ),
-        };
+        }
     }
-    pub fn mutate(self, mutation_rate: f32, device: &Device<B>) -> SumFxModel<B> {
-        let mut new_models: Vec<FF<B>> = vec![];
-
-        for model in [
+    pub fn mutate(
+        self,
+        mutation_rate: f32,
+        rng: &mut impl Rng,
+        device: &Device<B>,
+    ) -> SumFxModel<B> {
+        let new_models: Vec<FF<B>> = [
             self.being_model,
             self.fo_model,
             self.speechlet_model,
             self.self_model,
             self.final_model,
-        ] {
-            let mut newlins: Vec<Linear<B>> = vec![];
+        ]
+        .into_iter()
+        .map(|model| model.mutate(mutation_rate, rng, device))
+        .collect();
 
-            for lin in model.lins {
-                let [inp_size, outp_size] = lin.weight.shape().dims;
-                let mutation_lin = LinearConfig::new(inp_size, outp_size).init(device);
-                let newlin = combine_linears(lin, mutation_lin, 1., mutation_rate);
-                newlins.push(newlin);
-            }
+        let mutation_lstm = random_lstm(self.lstm_inp_size, self.lstm_inp_size, rng, device);
 
-            let new_model = FF {
-                lins: newlins,
-                acts: model.acts.clone(),
-            };
-            new_models.push(new_model);
-        }
-
-        let mutation_lstm =
-            LstmConfig::new(self.lstm_inp_size, self.lstm_inp_size, true).init(device);
-
-        return SumFxModel {
+        SumFxModel {
             being_model: new_models[0].to_owned(),
             fo_model: new_models[1].to_owned(),
             speechlet_model: new_models[2].to_owned(),
@@ -461,10 +614,618 @@ impl<B: Backend> SumFxModel<B> {
             intermediate_dim: self.intermediate_dim,
             lstm_inp_size: self.lstm_inp_size,
             state: (
-                Tensor::<B, 2>::zeros([1, self.intermediate_dim as usize], device),
-                Tensor::<B, 2>::zeros([1, self.intermediate_dim as usize], device),
+                Tensor::<B, 2>::zeros([1, self.intermediate_dim], device),
+                Tensor::<B, 2>::zeros([1, self.intermediate_dim], device),
             ),
+        }
+    }
+}
+
+// rows of an observation as a tensor, with a trailing row of -1s so that the mean taken over
+// rows is defined even when nothing is in sight
+pub(crate) fn tensorize_rows(rows: &[Vec<f32>], width: usize) -> Tensor<BACKEND, 2> {
+    let padding = vec![-1.; width];
+    let values: Vec<f32> = rows
+        .iter()
+        .chain([&padding])
+        .flatten()
+        .copied()
+        .collect();
+    Tensor::<BACKEND, 1>::from_floats(values.as_slice(), &DEVICE)
+        .reshape([rows.len() + 1, width])
+        .no_grad()
+}
+
+impl Brain for SumFxModel<BACKEND> {
+    type State = SumFxModelState;
+
+    fn random(rng: &mut WorldRng, widths: InputWidths) -> Self {
+        SumFxModel::standard_model(widths, rng, &DEVICE)
+    }
+
+    fn act(&mut self, obs: &Observation) -> [f32; B_OUTPUT_LEN] {
+        let widths = self.input_widths();
+        let being_tensor = tensorize_rows(obs.beings, widths.being);
+        let fo_tensor = tensorize_rows(obs.food_obstructs, widths.food_obstruct);
+        let speechlet_tensor = tensorize_rows(obs.speechlets, widths.speechlet);
+        let self_tensor = Tensor::<BACKEND, 1>::from_floats(obs.own.as_slice(), &DEVICE)
+            .reshape([1, 5])
+            .no_grad();
+
+        let model_output = self
+            .forward(being_tensor, fo_tensor, speechlet_tensor, self_tensor)
+            .into_data()
+            .value;
+
+        let mut output = [0.; B_OUTPUT_LEN];
+        output.copy_from_slice(&model_output[..B_OUTPUT_LEN]);
+        output
+    }
+
+    // models that share a layout, as all of one world's usually do, run as one batch
+    fn act_batch(brains: &mut [&mut Self], obs: &[Observation]) -> Vec<[f32; B_OUTPUT_LEN]> {
+        let mut layouts: Vec<Layout> = vec![];
+        let groups: Vec<usize> = brains
+            .iter()
+            .map(|b| {
+                let layout = b.layout();
+                layouts
+                    .iter()
+                    .position(|l| *l == layout)
+                    .unwrap_or_else(|| {
+                        layouts.push(layout);
+                        layouts.len() - 1
+                    })
+            })
+            .collect();
+
+        let mut outputs = vec![[0.; B_OUTPUT_LEN]; brains.len()];
+        for g in 0..layouts.len() {
+            let members: Vec<usize> = (0..brains.len()).filter(|i| groups[*i] == g).collect();
+            let mut models: Vec<&mut Self> = zip(brains.iter_mut(), &groups)
+                .filter(|(_, group)| **group == g)
+                .map(|(b, _)| &mut **b)
+                .collect();
+            let group_obs: Vec<&Observation> = members.iter().map(|i| &obs[*i]).collect();
+
+            for (i, output) in zip(members, act_group(&mut models, &group_obs)) {
+                outputs[i] = output;
+            }
+        }
+        outputs
+    }
+
+    fn reset(&mut self) {
+        self.reset_state(&DEVICE);
+    }
+
+    fn crossover(self, other: Self, weight: f32) -> Self {
+        SumFxModel::crossover(self, other, weight, &DEVICE)
+    }
+
+    fn mutate(self, rate: f32, rng: &mut WorldRng) -> Self {
+        SumFxModel::mutate(self, rate, rng, &DEVICE)
+    }
+
+    fn to_state(&self) -> SumFxModelState {
+        SumFxModel::to_state(self)
+    }
+
+    fn from_state(state: &SumFxModelState, widths: InputWidths) -> Result<Self, String> {
+        state.check_layout(widths)?;
+        SumFxModel::from_state(state, &DEVICE)
+    }
+}
+
+// batched inference: the models of many beings evaluated in shared tensor ops. weights are
+// stacked along a leading batch dimension and every op is the one forward does, in the same
+// order, so each being gets exactly the outputs and lstm state it would get on its own
+
+// what models must have in common to be batched: each layer's shape, bias and activation
+#[derive(PartialEq)]
+struct Layout {
+    ffs: Vec<Vec<(usize, usize, bool, ActivationName)>>,
+    lstm_gates: Vec<(bool, bool)>,
+    concat_before_final: bool,
+    intermediate_dim: usize,
+}
+
+impl<B: Backend> SumFxModel<B> {
+    // the row widths the sensory models were built for, it never looks at a retina
+    fn input_widths(&self) -> InputWidths {
+        let d_input = |ff: &FF<B>| ff.lins[0].weight.shape().dims[0];
+        InputWidths {
+            being: d_input(&self.being_model),
+            food_obstruct: d_input(&self.fo_model),
+            speechlet: d_input(&self.speechlet_model),
+            retina: 0,
+        }
+    }
+
+    fn layout(&self) -> Layout {
+        let ff_layout = |ff: &FF<B>| {
+            zip(&ff.lins, &ff.acts)
+                .map(|(lin, act)| {
+                    let [d_input, d_output] = lin.weight.shape().dims;
+                    (d_input, d_output, lin.bias.is_some(), act.clone().into())
+                })
+                .collect()
         };
+
+        Layout {
+            ffs: [
+                &self.being_model,
+                &self.fo_model,
+                &self.speechlet_model,
+                &self.self_model,
+                &self.final_model,
+            ]
+            .map(ff_layout)
+            .to_vec(),
+            lstm_gates: lstm_gates(&self.lstm)
+                .iter()
+                .map(|(i, h)| (i.bias.is_some(), h.bias.is_some()))
+                .collect(),
+            concat_before_final: self.concat_before_final,
+            intermediate_dim: self.intermediate_dim,
+        }
+    }
+}
+
+// one variable-length set of rows per being as a single [beings, rows, width] tensor. each set
+// gets its trailing row of -1s as in tensorize_rows, then zero rows up to the longest set
+pub struct RowSets<B: Backend> {
+    pub rows: Tensor<B, 3>,
+    mask: Tensor<B, 3>,   // [beings, rows, 1], 1 for the rows a being really has
+    counts: Tensor<B, 3>, // [beings, 1, 1]
+}
+
+impl<B: Backend> RowSets<B> {
+    pub fn new<'a>(
+        sets: impl ExactSizeIterator<Item = &'a [Vec<f32>]> + Clone,
+        width: usize,
+        device: &Device<B>,
+    ) -> Self {
+        let n = sets.len();
+        let n_rows = sets.clone().map(|set| set.len() + 1).max().unwrap_or(1);
+
+        let mut values = vec![0.; n * n_rows * width];
+        let mut mask = vec![0.; n * n_rows];
+        let mut counts = vec![0.; n];
+        for (i, set) in sets.enumerate() {
+            let padding = vec![-1.; width];
+            for (r, row) in set.iter().chain([&padding]).enumerate() {
+                let at = (i * n_rows + r) * width;
+                values[at..at + width].copy_from_slice(row);
+                mask[i * n_rows + r] = 1.;
+            }
+            counts[i] = (set.len() + 1) as f32;
+        }
+
+        RowSets {
+            rows: Tensor::<B, 1>::from_floats(values.as_slice(), device)
+                .reshape([n, n_rows, width])
+                .no_grad(),
+            mask: Tensor::<B, 1>::from_floats(mask.as_slice(), device)
+                .reshape([n, n_rows, 1])
+                .no_grad(),
+            counts: Tensor::<B, 1>::from_floats(counts.as_slice(), device)
+                .reshape([n, 1, 1])
+                .no_grad(),
+        }
+    }
+
+    // x's mean over each being's own rows, as mean_dim(0) over a single being's rows
+    fn mean(&self, x: Tensor<B, 3>) -> Tensor<B, 3> {
+        (x * self.mask.clone()).sum_dim(1) / self.counts.clone()
+    }
+}
+
+fn stack_weights<B: Backend>(lins: &[&Linear<B>]) -> Tensor<B, 3> {
+    Tensor::stack(lins.iter().map(|l| l.weight.val()).collect(), 0)
+}
+
+// [beings, 1, d_output], or None for linears without bias
+fn stack_biases<B: Backend>(lins: &[&Linear<B>]) -> Option<Tensor<B, 3>> {
+    lins[0].bias.as_ref()?;
+    let biases: Vec<Tensor<B, 1>> = lins
+        .iter()
+        .map(|l| l.bias.as_ref().unwrap().val())
+        .collect();
+    Some(Tensor::stack::<2>(biases, 0).unsqueeze_dim(1))
+}
+
+// every being's ff applied to its own rows of x, as FF::forward
+fn forward_ffs<B: Backend>(ffs: &[&FF<B>], mut x: Tensor<B, 3>) -> Tensor<B, 3> {
+    for (l, act) in ffs[0].acts.iter().enumerate().take(ffs[0].lins.len()) {
+        let lins: Vec<&Linear<B>> = ffs.iter().map(|ff| &ff.lins[l]).collect();
+        x = x.matmul(stack_weights(&lins));
+        if let Some(bias) = stack_biases(&lins) {
+            x = x + bias;
+        }
+        x = act.forward(x);
+    }
+    x
+}
+
+// as burn's Lstm::gate_product, terms added in the same order
+fn gate_products<B: Backend>(
+    gates: &[&(Linear<B>, Linear<B>)],
+    input: Tensor<B, 3>,
+    hidden: Tensor<B, 3>,
+) -> Tensor<B, 3> {
+    let input_transforms: Vec<&Linear<B>> = gates.iter().map(|(i, _)| i).collect();
+    let hidden_transforms: Vec<&Linear<B>> = gates.iter().map(|(_, h)| h).collect();
+
+    let mut sum = input.matmul(stack_weights(&input_transforms));
+    if let Some(bias) = stack_biases(&input_transforms) {
+        sum = sum + bias;
+    }
+    sum = sum + hidden.matmul(stack_weights(&hidden_transforms));
+    if let Some(bias) = stack_biases(&hidden_transforms) {
+        sum = sum + bias;
+    }
+    sum
+}
+
+impl<B: Backend> SumFxModel<B> {
+    // forward for several models of one layout, each on its own observation. self_tensor is
+    // [models, 1, 5], the result [models, 1, output width]
+    pub fn forward_batch(
+        models: &mut [&mut SumFxModel<B>],
+        beings: RowSets<B>,
+        fo: RowSets<B>,
+        speechlets: RowSets<B>,
+        self_tensor: Tensor<B, 3>,
+    ) -> Tensor<B, 3> {
+        let n = models.len();
+        let ffs = |pick: fn(&SumFxModel<B>) -> &FF<B>| -> Vec<&FF<B>> {
+            models.iter().map(|m| pick(m)).collect()
+        };
+
+        let beings_output = beings.mean(forward_ffs(&ffs(|m| &m.being_model), beings.rows.clone()));
+        let fo_output = fo.mean(forward_ffs(&ffs(|m| &m.fo_model), fo.rows.clone()));
+        let speechlet_output = speechlets.mean(forward_ffs(
+            &ffs(|m| &m.speechlet_model),
+            speechlets.rows.clone(),
+        ));
+        let self_output = forward_ffs(&ffs(|m| &m.self_model), self_tensor);
+
+        let intermediate: Tensor<B, 3> = if models[0].concat_before_final {
+            Tensor::cat(
+                vec![beings_output, fo_output, speechlet_output, self_output],
+                2,
+            )
+        } else {
+            (beings_output + fo_output + speechlet_output + self_output) / 4.
+        };
+
+        let dim = models[0].intermediate_dim;
+        let stack_state = |pick: fn(&SumFxModel<B>) -> Tensor<B, 2>| -> Tensor<B, 3> {
+            Tensor::cat(models.iter().map(|m| pick(m)).collect(), 0).reshape([n, 1, dim])
+        };
+        let c = stack_state(|m| m.state.0.clone());
+        let h = stack_state(|m| m.state.1.clone());
+
+        let gates: Vec<Vec<(Linear<B>, Linear<B>)>> =
+            models.iter().map(|m| lstm_gates(&m.lstm)).collect();
+        let gate = |g: usize| {
+            let gate: Vec<&(Linear<B>, Linear<B>)> = gates.iter().map(|gs| &gs[g]).collect();
+            gate_products(&gate, intermediate.clone(), h.clone())
+        };
+        let forget_values = activation::sigmoid(gate(1));
+        let add_values = activation::sigmoid(gate(0));
+        let output_values = activation::sigmoid(gate(2));
+        let candidate_cell_values = gate(3).tanh();
+
+        let c = forget_values * c + add_values * candidate_cell_values;
+        let h = output_values * c.clone().tanh();
+
+        let final_output = forward_ffs(&ffs(|m| &m.final_model), h.clone());
+
+        for (i, m) in models.iter_mut().enumerate() {
+            m.state = (
+                c.clone().narrow(0, i, 1).reshape([1, dim]).no_grad(),
+                h.clone().narrow(0, i, 1).reshape([1, dim]).no_grad(),
+            );
+        }
+
+        activation::tanh(final_output)
+    }
+}
+
+// one group of act_batch, all of one layout
+fn act_group(
+    models: &mut [&mut SumFxModel<BACKEND>],
+    obs: &[&Observation],
+) -> Vec<[f32; B_OUTPUT_LEN]> {
+    let widths = models[0].input_widths();
+    let beings = RowSets::new(obs.iter().map(|o| o.beings), widths.being, &DEVICE);
+    let fo = RowSets::new(
+        obs.iter().map(|o| o.food_obstructs),
+        widths.food_obstruct,
+        &DEVICE,
+    );
+    let speechlets = RowSets::new(obs.iter().map(|o| o.speechlets), widths.speechlet, &DEVICE);
+    let own: Vec<f32> = obs.iter().flat_map(|o| o.own).collect();
+    let self_tensor = Tensor::<BACKEND, 1>::from_floats(own.as_slice(), &DEVICE)
+        .reshape([obs.len(), 1, 5])
+        .no_grad();
+
+    let model_output = SumFxModel::forward_batch(models, beings, fo, speechlets, self_tensor)
+        .into_data()
+        .value;
+
+    model_output
+        .chunks(B_OUTPUT_LEN)
+        .map(|values| {
+            let mut output = [0.; B_OUTPUT_LEN];
+            output.copy_from_slice(values);
+            output
+        })
+        .collect()
+}
+
+pub(crate) fn tensor_values<B: Backend, const D: usize>(tensor: Tensor<B, D>) -> Vec<f32> {
+    tensor.into_data().convert::<f32>().value
+}
+
+// plain-data mirrors of the model types, what snapshots and brain files are made of
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinearState {
+    pub d_input: usize,
+    pub d_output: usize,
+    pub weight: Vec<f32>,
+    pub bias: Option<Vec<f32>>,
+}
+
+impl LinearState {
+    pub fn from_linear<B: Backend>(lin: &Linear<B>) -> Self {
+        let [d_input, d_output] = lin.weight.shape().dims;
+        LinearState {
+            d_input,
+            d_output,
+            weight: tensor_values(lin.weight.val()),
+            bias: lin.bias.as_ref().map(|b| tensor_values(b.val())),
+        }
+    }
+
+    pub fn to_linear<B: Backend>(&self, device: &Device<B>) -> Result<Linear<B>, String> {
+        if self.weight.len() != self.d_input * self.d_output
+            || self.bias.as_ref().is_some_and(|b| b.len() != self.d_output)
+        {
+            return Err(format!(
+                "linear {}x{} does not match its stored values",
+                self.d_input, self.d_output
+            ));
+        }
+
+        Ok(Linear {
+            weight: Param::from_tensor(
+                Tensor::<B, 1>::from_floats(self.weight.as_slice(), device)
+                    .reshape([self.d_input, self.d_output]),
+            ),
+            bias: self
+                .bias
+                .as_ref()
+                .map(|b| Param::from_tensor(Tensor::<B, 1>::from_floats(b.as_slice(), device))),
+        }
+        .no_grad())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FFState {
+    pub lins: Vec<LinearState>,
+    pub acts: Vec<Activation>,
+}
+
+impl FFState {
+    pub fn from_ff<B: Backend>(ff: &FF<B>) -> Self {
+        FFState {
+            lins: ff.lins.iter().map(LinearState::from_linear).collect(),
+            acts: ff.acts.clone(),
+        }
+    }
+
+    pub fn to_ff<B: Backend>(&self, device: &Device<B>) -> Result<FF<B>, String> {
+        Ok(FF {
+            lins: self
+                .lins
+                .iter()
+                .map(|l| l.to_linear(device))
+                .collect::<Result<_, _>>()?,
+            acts: self.acts.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LstmState {
+    pub d_input: usize,
+    pub d_hidden: usize,
+    // (input_transform, hidden_transform) for the input, forget, output and cell gates
+    pub gates: Vec<(LinearState, LinearState)>,
+}
+
+impl LstmState {
+    pub fn from_lstm<B: Backend>(lstm: &Lstm<B>) -> Self {
+        let gates: Vec<(LinearState, LinearState)> = lstm_gates(lstm)
+            .iter()
+            .map(|(i, h)| (LinearState::from_linear(i), LinearState::from_linear(h)))
+            .collect();
+
+        LstmState {
+            d_input: gates[0].0.d_input,
+            d_hidden: gates[0].0.d_output,
+            gates,
+        }
+    }
+
+    pub fn to_lstm<B: Backend>(&self, device: &Device<B>) -> Result<Lstm<B>, String> {
+        if self.gates.len() != 4 {
+            return Err(format!("lstm needs 4 gates, found {}", self.gates.len()));
+        }
+        let gates = self
+            .gates
+            .iter()
+            .map(|(i, h)| Ok((i.to_linear(device)?, h.to_linear(device)?)))
+            .collect::<Result<_, String>>()?;
+
+        Ok(lstm_from_gates(self.d_input, self.d_hidden, gates, device))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SumFxModelState {
+    pub being_model: FFState,
+    pub fo_model: FFState,
+    pub speechlet_model: FFState,
+    pub self_model: FFState,
+
+    pub lstm: LstmState,
+    pub final_model: FFState,
+
+    pub concat_before_final: bool,
+    pub intermediate_dim: usize,
+    pub lstm_inp_size: usize,
+
+    pub state: (Vec<f32>, Vec<f32>), // lstm (cell, hidden) carried between steps
+}
+
+impl FFState {
+    // input and output width, after checking each layer feeds the next
+    pub(crate) fn dims(&self, name: &str) -> Result<(usize, usize), String> {
+        let (first, last) = match (self.lins.first(), self.lins.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(format!("{} has no layers", name)),
+        };
+        if self.acts.len() != self.lins.len() + 1 {
+            return Err(format!(
+                "{} has {} layers but {} activations",
+                name,
+                self.lins.len(),
+                self.acts.len()
+            ));
+        }
+        for (a, b) in zip(&self.lins, &self.lins[1..]) {
+            if a.d_output != b.d_input {
+                return Err(format!(
+                    "{} layer of width {} feeds a layer expecting {}",
+                    name, a.d_output, b.d_input
+                ));
+            }
+        }
+        Ok((first.d_input, last.d_output))
+    }
+}
+
+impl SumFxModelState {
+    // from_state only checks that stored values fit their own shapes, this checks that the
+    // layers fit each other and the world's input and output widths, as a brain file from
+    // another build might not
+    pub fn check_layout(&self, widths: InputWidths) -> Result<(), String> {
+        let mut sensory_out = vec![];
+        for (name, ff, d_in) in [
+            ("being_model", &self.being_model, widths.being),
+            ("fo_model", &self.fo_model, widths.food_obstruct),
+            ("speechlet_model", &self.speechlet_model, widths.speechlet),
+            ("self_model", &self.self_model, 5),
+        ] {
+            let (i, o) = ff.dims(name)?;
+            if i != d_in {
+                return Err(format!("{} takes {} inputs, the world gives {}", name, i, d_in));
+            }
+            sensory_out.push(o);
+        }
+
+        let intermediate_dim = if self.concat_before_final {
+            sensory_out.iter().sum()
+        } else if sensory_out.iter().all(|o| *o == sensory_out[0]) {
+            sensory_out[0]
+        } else {
+            return Err("sensory models must share an output width in add mode".into());
+        };
+        if intermediate_dim != self.intermediate_dim || intermediate_dim != self.lstm_inp_size {
+            return Err(format!(
+                "sensory models output {} but the model expects {}",
+                intermediate_dim, self.intermediate_dim
+            ));
+        }
+        if self.lstm.d_input != self.lstm_inp_size || self.lstm.d_hidden != self.lstm_inp_size {
+            return Err(format!(
+                "lstm is {}x{}, expected {}x{}",
+                self.lstm.d_input, self.lstm.d_hidden, self.lstm_inp_size, self.lstm_inp_size
+            ));
+        }
+
+        let (i, o) = self.final_model.dims("final_model")?;
+        if i != self.lstm_inp_size || o != B_OUTPUT_LEN {
+            return Err(format!(
+                "final_model is {}->{}, expected {}->{}",
+                i, o, self.lstm_inp_size, B_OUTPUT_LEN
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<B: Backend> SumFxModel<B> {
+    pub fn to_state(&self) -> SumFxModelState {
+        SumFxModelState {
+            being_model: FFState::from_ff(&self.being_model),
+            fo_model: FFState::from_ff(&self.fo_model),
+            speechlet_model: FFState::from_ff(&self.speechlet_model),
+            self_model: FFState::from_ff(&self.self_model),
+
+            lstm: LstmState::from_lstm(&self.lstm),
+            final_model: FFState::from_ff(&self.final_model),
+
+            concat_before_final: self.concat_before_final,
+            intermediate_dim: self.intermediate_dim,
+            lstm_inp_size: self.lstm_inp_size,
+
+            state: (
+                tensor_values(self.state.0.clone()),
+                tensor_values(self.state.1.clone()),
+            ),
+        }
+    }
+
+    // forget what the lstm carried over, as for a newborn
+    pub fn reset_state(&mut self, device: &Device<B>) {
+        self.state = (
+            Tensor::<B, 2>::zeros([1, self.intermediate_dim], device),
+            Tensor::<B, 2>::zeros([1, self.intermediate_dim], device),
+        );
+    }
+
+    pub fn from_state(state: &SumFxModelState, device: &Device<B>) -> Result<Self, String> {
+        let (c, h) = &state.state;
+        if c.len() != state.intermediate_dim || h.len() != state.intermediate_dim {
+            return Err("lstm state does not match intermediate_dim".into());
+        }
+
+        Ok(SumFxModel {
+            being_model: state.being_model.to_ff(device)?,
+            fo_model: state.fo_model.to_ff(device)?,
+            speechlet_model: state.speechlet_model.to_ff(device)?,
+            self_model: state.self_model.to_ff(device)?,
+
+            lstm: state.lstm.to_lstm(device)?,
+            final_model: state.final_model.to_ff(device)?,
+
+            concat_before_final: state.concat_before_final,
+            intermediate_dim: state.intermediate_dim,
+            lstm_inp_size: state.lstm_inp_size,
+
+            state: (
+                Tensor::<B, 1>::from_floats(c.as_slice(), device).reshape([1, c.len()]),
+                Tensor::<B, 1>::from_floats(h.as_slice(), device).reshape([1, h.len()]),
+            ),
+        })
     }
 }
 
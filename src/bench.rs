@@ -0,0 +1,177 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{brain::Brain, World};
+
+// the parts of World::step that are timed separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    MoveBeings,
+    CheckCollisions,
+    UpdateCells,
+    PerformBeingOutputs,
+    GrowSpeechlets,
+    TireBeings,
+    Aging, // age_foods, age_obstructs and soften_speechlets
+    RepopFoods,
+    Reworld,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 9] = [
+        Phase::MoveBeings,
+        Phase::CheckCollisions,
+        Phase::UpdateCells,
+        Phase::PerformBeingOutputs,
+        Phase::GrowSpeechlets,
+        Phase::TireBeings,
+        Phase::Aging,
+        Phase::RepopFoods,
+        Phase::Reworld,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::MoveBeings => "move_beings",
+            Phase::CheckCollisions => "check_collisions",
+            Phase::UpdateCells => "update_cells",
+            Phase::PerformBeingOutputs => "perform_being_outputs",
+            Phase::GrowSpeechlets => "grow_speechlets",
+            Phase::TireBeings => "tire_beings",
+            Phase::Aging => "aging",
+            Phase::RepopFoods => "repop_foods",
+            Phase::Reworld => "reworld",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PhaseTimings {
+    totals: [Duration; Phase::ALL.len()],
+}
+
+impl PhaseTimings {
+    pub fn get(&self, phase: Phase) -> Duration {
+        self.totals[phase as usize]
+    }
+}
+
+// runs f, adding its wall time to phase when timings are being taken
+pub fn run_phase<T>(
+    timings: &mut Option<&mut PhaseTimings>,
+    phase: Phase,
+    f: impl FnOnce() -> T,
+) -> T {
+    match timings {
+        None => f(),
+        Some(t) => {
+            let start = Instant::now();
+            let out = f();
+            t.totals[phase as usize] += start.elapsed();
+            out
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseReport {
+    pub phase: &'static str,
+    pub total_ms: f64,
+    pub per_tick_us: f64,
+    pub share: f64,
+}
+
+// what a bench run reports, also written as json so runs can be compared across commits
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub seed: u64,
+    pub ticks: usize,
+    pub batch_inference: bool,
+    pub parallel_collisions: bool,
+    pub generations: usize,
+    pub beings_at_end: usize,
+    pub total_ms: f64,
+    pub ticks_per_sec: f64,
+    pub phases: Vec<PhaseReport>,
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} ticks, {} generations in {:.1}ms, {:.1} ticks/s (seed {}, {} beings at the end{}{})",
+            self.ticks,
+            self.generations,
+            self.total_ms,
+            self.ticks_per_sec,
+            self.seed,
+            self.beings_at_end,
+            if self.batch_inference {
+                ""
+            } else {
+                ", per-being inference"
+            },
+            if self.parallel_collisions {
+                ""
+            } else {
+                ", serial collisions"
+            }
+        )?;
+        for p in &self.phases {
+            writeln!(
+                f,
+                "  {:<24}{:>10.1}ms{:>10.1}us/tick{:>7.1}%",
+                p.phase,
+                p.total_ms,
+                p.per_tick_us,
+                p.share * 100.
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// steps the world a fixed number of ticks, timing each phase of every step. all the beings'
+// randomness comes from the world's seed, so the same seed and config always do the same work
+pub fn bench<const D: usize, M: Brain>(world: &mut World<D, M>, ticks: usize) -> BenchReport {
+    let mut timings = PhaseTimings::default();
+    let mut generations = 0;
+
+    let start = Instant::now();
+    for _ in 0..ticks {
+        if world.step_profiled(1, Some(&mut timings)).is_some() {
+            generations += 1;
+        }
+    }
+    let total = start.elapsed();
+
+    let phase_total: Duration = timings.totals.iter().sum();
+    let phases = Phase::ALL
+        .iter()
+        .map(|p| {
+            let d = timings.get(*p);
+            PhaseReport {
+                phase: p.name(),
+                total_ms: d.as_secs_f64() * 1e3,
+                per_tick_us: d.as_secs_f64() * 1e6 / ticks.max(1) as f64,
+                share: d.as_secs_f64() / phase_total.as_secs_f64().max(f64::MIN_POSITIVE),
+            }
+        })
+        .collect();
+
+    BenchReport {
+        seed: world.seed,
+        ticks,
+        batch_inference: world.batch_inference,
+        parallel_collisions: world.parallel_collisions,
+        generations,
+        beings_at_end: world.beings_and_models.len(),
+        total_ms: total.as_secs_f64() * 1e3,
+        ticks_per_sec: ticks as f64 / total.as_secs_f64(),
+        phases,
+    }
+}
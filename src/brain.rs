@@ -0,0 +1,153 @@
+use std::{fmt, fs, io, iter::zip, path::Path};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{being_nn::SumFxModelState, consts::*};
+
+// what a being perceives in one tick, gathered by check_collisions. each row describes one
+// thing in sight relative to the being, any of the three lists may be empty. row widths are
+// the world's InputWidths
+pub struct Observation<'a> {
+    pub beings: &'a [Vec<f32>],         // see b_collides_b
+    pub food_obstructs: &'a [Vec<f32>], // first value 1 for food and 0 for obstructs
+    pub speechlets: &'a [Vec<f32>],     // see speechlet_row
+    pub own: [f32; 5],                  // is_border_in_sight, then energy / b_start_energy
+    pub retina: &'a [f32],              // RETINA_CHANNELS per ray, empty without rays
+}
+
+// how many values each row of an Observation holds, which depends on the world's config.
+// see SimConfig::input_widths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputWidths {
+    pub being: usize,
+    pub food_obstruct: usize,
+    pub speechlet: usize,
+    pub retina: usize, // not rows but one fixed-size vector, 0 when no rays are cast
+}
+
+// whatever decides what a being does. the world only ever talks to its beings through this,
+// so scripted controllers or other architectures can stand in for SumFxModel
+pub trait Brain: Clone + Sized {
+    // plain data the brain is saved as in snapshots, checkpoints and brain files
+    type State: Serialize + DeserializeOwned + Clone;
+
+    // a fresh brain for the first generation of a world whose observations are this wide
+    fn random(rng: &mut WorldRng, widths: InputWidths) -> Self;
+
+    // one tick: what the being sees in, its B_OUTPUT_LEN outputs out. outputs are read as
+    // (forward-backward, rotate, spawn obstruct if > 0, speak if > 0, *speechlet)
+    fn act(&mut self, obs: &Observation) -> [f32; B_OUTPUT_LEN];
+
+    // one tick for many beings, outputs in the order of brains and obs. brains that can share
+    // work across beings override this, but must give exactly what act would give each in turn
+    fn act_batch(brains: &mut [&mut Self], obs: &[Observation]) -> Vec<[f32; B_OUTPUT_LEN]> {
+        zip(brains, obs).map(|(b, o)| b.act(o)).collect()
+    }
+
+    // whether a world whose observations are this wide gives the brain what it needs
+    fn check_widths(_widths: InputWidths) -> Result<(), String> {
+        Ok(())
+    }
+
+    // forget anything carried between ticks, as for a newborn
+    fn reset(&mut self);
+
+    // a child of two parents, weight being how much of other it takes after
+    fn crossover(self, other: Self, weight: f32) -> Self;
+
+    fn mutate(self, rate: f32, rng: &mut WorldRng) -> Self;
+
+    fn to_state(&self) -> Self::State;
+
+    // rejects states that do not fit together or do not fit the world's input/output widths
+    fn from_state(state: &Self::State, widths: InputWidths) -> Result<Self, String>;
+}
+
+// bumped whenever SumFxModelState changes shape, old files are refused rather than misread
+pub const BRAIN_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum BrainError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    Version { found: u32, expected: u32 },
+    Invalid(String),
+}
+
+impl fmt::Display for BrainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrainError::Io(e) => write!(f, "brain file io failed: {}", e),
+            BrainError::Format(e) => write!(f, "malformed brain file: {}", e),
+            BrainError::Version { found, expected } => write!(
+                f,
+                "brain file version {} can not be loaded, expected {}",
+                found, expected
+            ),
+            BrainError::Invalid(e) => write!(f, "invalid brain: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BrainError {}
+
+impl From<io::Error> for BrainError {
+    fn from(e: io::Error) -> Self {
+        BrainError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for BrainError {
+    fn from(e: serde_json::Error) -> Self {
+        BrainError::Format(e)
+    }
+}
+
+// one being's brain, layout included, plus where it came from. pretty-printed so that
+// archived champions can be told apart without loading them
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "S: Serialize + DeserializeOwned")]
+pub struct BrainFile<S = SumFxModelState> {
+    pub version: u32,
+
+    pub seed: u64,
+    pub generation: usize,
+    pub being_id: usize,
+
+    pub model: S,
+}
+
+impl<S: Serialize + DeserializeOwned> BrainFile<S> {
+    pub fn new<M: Brain<State = S>>(model: &M, seed: u64, generation: usize, being_id: usize) -> Self {
+        BrainFile {
+            version: BRAIN_VERSION,
+            seed,
+            generation,
+            being_id,
+            model: model.to_state(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), BrainError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, BrainError> {
+        let brain: BrainFile<S> = serde_json::from_slice(&fs::read(path)?)?;
+        if brain.version != BRAIN_VERSION {
+            return Err(BrainError::Version {
+                found: brain.version,
+                expected: BRAIN_VERSION,
+            });
+        }
+        Ok(brain)
+    }
+
+    // starts out blank, whatever the being was thinking when it was exported
+    pub fn to_model<M: Brain<State = S>>(&self, widths: InputWidths) -> Result<M, BrainError> {
+        let mut model = M::from_state(&self.model, widths).map_err(BrainError::Invalid)?;
+        model.reset();
+        Ok(model)
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    brain::Brain,
+    config::SimConfig,
+    consts::*,
+    snapshot::SnapshotError,
+    World,
+};
+
+// bumped whenever the layout below changes, old files are refused rather than misread
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+// what evolution has produced so far, without the state of any one generation. much smaller
+// than a snapshot, resuming one starts a fresh generation bred from the stored survivors
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "S: Serialize + DeserializeOwned")]
+struct Checkpoint<S> {
+    version: u32,
+
+    config: SimConfig,
+    seed: u64,
+    rng: WorldRng,
+
+    generation: usize,
+    max_food: usize,
+    survivors: Vec<S>,
+    #[serde(default)]
+    survivor_energies: Vec<f32>,
+    #[serde(default)]
+    survivor_clans: Vec<usize>,
+}
+
+// checkpoints are written to dir every few generations, only the newest few are kept
+#[derive(Debug, Clone)]
+pub struct CheckpointPolicy {
+    pub dir: PathBuf,
+    pub every: usize,
+    pub keep: usize,
+}
+
+fn checkpoint_path(dir: &Path, generation: usize) -> PathBuf {
+    dir.join(format!("checkpoint-{:06}.json", generation))
+}
+
+// checkpoints in dir by generation, oldest first
+fn list_checkpoints(dir: &Path) -> Result<Vec<(usize, PathBuf)>, SnapshotError> {
+    let mut found = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let generation = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_prefix("checkpoint-")?.strip_suffix(".json"))
+            .and_then(|g| g.parse().ok());
+        if let Some(generation) = generation {
+            found.push((generation, path));
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+pub fn latest_checkpoint(dir: &Path) -> Result<PathBuf, SnapshotError> {
+    list_checkpoints(dir)?
+        .pop()
+        .map(|(_, path)| path)
+        .ok_or_else(|| SnapshotError::Invalid(format!("no checkpoints in {}", dir.display())))
+}
+
+impl CheckpointPolicy {
+    pub fn due(&self, generation: usize) -> bool {
+        generation.is_multiple_of(self.every)
+    }
+
+    // writes a checkpoint of the world's current generation, then drops all but the newest keep
+    pub fn write<const D: usize, M: Brain>(&self, world: &World<D, M>) -> Result<PathBuf, SnapshotError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = checkpoint_path(&self.dir, world.generation);
+        world.save_checkpoint(&path)?;
+
+        let found = list_checkpoints(&self.dir)?;
+        for (_, old) in &found[..found.len().saturating_sub(self.keep)] {
+            fs::remove_file(old)?;
+        }
+        Ok(path)
+    }
+}
+
+impl<const D: usize, M: Brain> World<D, M> {
+    // the survivors the current generation was bred from, along with the food schedule
+    pub fn save_checkpoint(&self, path: &Path) -> Result<(), SnapshotError> {
+        let checkpoint = Checkpoint {
+            version: CHECKPOINT_VERSION,
+
+            config: self.config.clone(),
+            seed: self.seed,
+            rng: self.rng.clone(),
+
+            generation: self.generation,
+            max_food: self.max_food,
+            survivors: self.last_survivors.iter().map(|m| m.to_state()).collect(),
+            survivor_energies: self.last_survivor_energies.clone(),
+            survivor_clans: self.last_survivor_clans.clone(),
+        };
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&checkpoint)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn load_checkpoint(path: &Path) -> Result<Self, SnapshotError> {
+        let checkpoint: Checkpoint<M::State> = serde_json::from_slice(&fs::read(path)?)?;
+        if checkpoint.version != CHECKPOINT_VERSION {
+            return Err(SnapshotError::Version {
+                found: checkpoint.version,
+                expected: CHECKPOINT_VERSION,
+            });
+        }
+        checkpoint
+            .config
+            .validate()
+            .map_err(|e| SnapshotError::Invalid(e.to_string()))?;
+        if checkpoint.survivors.is_empty() {
+            return Err(SnapshotError::Invalid(
+                "checkpoint holds no survivors".into(),
+            ));
+        }
+
+        let survivors: Vec<M> = checkpoint
+            .survivors
+            .iter()
+            .map(|s| M::from_state(s, checkpoint.config.input_widths()))
+            .collect::<Result<_, _>>()
+            .map_err(SnapshotError::Invalid)?;
+
+        let mut world = World::new(checkpoint.config, checkpoint.seed);
+        world.rng = checkpoint.rng;
+        world.generation = checkpoint.generation;
+        world.max_food = checkpoint.max_food;
+
+        world.last_survivors = survivors;
+        world.last_survivor_energies = checkpoint.survivor_energies;
+        world.last_survivor_clans = checkpoint.survivor_clans;
+
+        let parents = world.clanned_survivors();
+        let mut models = parents.clone();
+        models.extend(world.breed(&parents));
+        world.start_generation(models);
+
+        Ok(world)
+    }
+}
@@ -0,0 +1,177 @@
+use std::{num::NonZeroUsize, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use neuralang::events::EventType;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "samsarsa",
+    about = "neural beings that eat, build walls and speak"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run a world in a window
+    View {
+        #[command(flatten)]
+        world: WorldArgs,
+
+        /// Draw only every n-th generation, the rest run with a blank window
+        #[arg(long, default_value_t = NonZeroUsize::MIN)]
+        vis_frequency: NonZeroUsize,
+    },
+    /// Run evolution without a window until a generation or tick limit
+    Train {
+        #[command(flatten)]
+        world: WorldArgs,
+
+        #[arg(long, required_unless_present = "ticks")]
+        generations: Option<usize>,
+
+        #[arg(long)]
+        ticks: Option<usize>,
+
+        /// Directory the run's seed and config are written to, along with snapshots
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+
+        /// Overwrite <OUT_DIR>/snapshot.json every this many ticks and when the run ends
+        #[arg(long, requires = "out_dir")]
+        snapshot_every: Option<NonZeroUsize>,
+
+        /// Checkpoint the survivors to <OUT_DIR>/checkpoints every this many generations and on ctrl-c
+        #[arg(long, requires = "out_dir")]
+        checkpoint_every: Option<NonZeroUsize>,
+
+        /// How many of the newest checkpoints to keep
+        #[arg(long, default_value_t = NonZeroUsize::new(5).unwrap())]
+        keep_checkpoints: NonZeroUsize,
+
+        /// Append a record per generation to this .csv or .jsonl file
+        #[arg(long)]
+        stats: Option<PathBuf>,
+
+        /// Write in-world interactions to this .jsonl file
+        #[arg(long)]
+        events: Option<PathBuf>,
+
+        /// Interactions to write to --events (eat, bump, oob, death, wall, speak, hear), all if omitted
+        #[arg(long, value_delimiter = ',', requires = "events")]
+        event_types: Vec<EventType>,
+    },
+    /// Evolve several worlds at once, one per thread, passing their best survivors around a ring.
+    /// Island n is seeded with the given seed plus n
+    Islands {
+        #[command(flatten)]
+        world: WorldArgs,
+
+        #[arg(long, default_value_t = NonZeroUsize::new(4).unwrap())]
+        islands: NonZeroUsize,
+
+        /// Generations each island evolves for
+        #[arg(long)]
+        generations: usize,
+
+        /// Generations between migrations
+        #[arg(long, default_value_t = NonZeroUsize::new(10).unwrap())]
+        migrate_every: NonZeroUsize,
+
+        /// How many of its best survivors each island sends to the next at every migration
+        #[arg(long, default_value_t = 2)]
+        migrants: usize,
+
+        /// Directory to write each island's generation records to, as island-<n>.csv
+        #[arg(long)]
+        stats_dir: Option<PathBuf>,
+    },
+    /// Step a world a fixed number of ticks and report time spent per phase, seed 0 if not given
+    Bench {
+        #[command(flatten)]
+        world: WorldArgs,
+
+        #[arg(long, default_value_t = 1000)]
+        ticks: usize,
+
+        /// Also write the report to this json file
+        #[arg(long)]
+        json: Option<PathBuf>,
+
+        /// Run each being's brain on its own instead of batching them, to compare timings
+        #[arg(long)]
+        per_being: bool,
+
+        /// Check collisions on one thread instead of rayon's pool, to compare timings
+        #[arg(long)]
+        serial_collisions: bool,
+    },
+    /// Re-simulate a seeded run up to a generation without drawing, then view it from there
+    Replay {
+        #[command(flatten)]
+        world: WorldArgs,
+
+        #[arg(long)]
+        generation: usize,
+
+        #[arg(long, default_value_t = NonZeroUsize::MIN)]
+        vis_frequency: NonZeroUsize,
+    },
+    /// Write one being's brain from a snapshot to a file that --brain can seed worlds with
+    ExportBrain {
+        /// Snapshot to take the being from
+        snapshot: PathBuf,
+
+        /// Brain file to write
+        out: PathBuf,
+
+        /// Id of the being to export, the one with the most energy otherwise
+        #[arg(long)]
+        being: Option<usize>,
+    },
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct WorldArgs {
+    /// A .toml or .json SimConfig, defaults are used otherwise
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+
+    /// Seed for every random draw in the world, a fresh one is drawn and printed if omitted
+    #[arg(long, short)]
+    pub seed: Option<u64>,
+
+    /// Resume the world saved in a snapshot file instead of starting a new one
+    #[arg(long, conflicts_with_all = ["config", "seed"])]
+    pub snapshot: Option<PathBuf>,
+
+    /// Start the population from this exported brain, can be given several times
+    #[arg(long, conflicts_with = "snapshot")]
+    pub brain: Vec<PathBuf>,
+
+    /// Breed a new generation from the newest checkpoint in <RESUME>/checkpoints
+    #[arg(long, conflicts_with_all = ["config", "seed", "snapshot", "brain"])]
+    pub resume: Option<PathBuf>,
+
+    /// What drives the beings, the scripted ones give baselines to compare evolved stats against
+    #[arg(long, value_enum, default_value_t)]
+    pub controller: Controller,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Controller {
+    /// Evolved neural networks
+    #[default]
+    Neural,
+    /// Uniformly random outputs
+    Random,
+    /// Turns toward the nearest food in sight
+    GreedyForager,
+    /// Backs away laying walls while it has energy to spare
+    WallBuilder,
+    /// Evolved neural networks that see through ray casts, needs b_retina_rays in the config
+    Retina,
+}
@@ -0,0 +1,385 @@
+use std::{fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{brain::InputWidths, consts::*};
+
+// every runtime tunable of a world. fields mirror the old compile-time consts, lowercased.
+// a config file only needs to name the fields it changes, the rest fall back to Default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimConfig {
+    pub w_size: usize,
+    pub n_cells: usize,
+
+    pub b_start_count: usize,
+    pub reworlding_threshold: usize,
+
+    pub b_fov: isize, // sensing radius in cells, collisions only look as far as contact_reach
+    pub b_view_half_angle_degrees: f32, // sight either side of facing, 180 sees all around
+    pub b_view_range: f32, // how far sight reaches in cells, up to b_fov, 0 for all of it
+    pub b_blind_half_angle_degrees: f32, // blind either side of straight behind
+    pub b_occlusion: Occlusion, // what obstructs do to the things behind them
+    pub b_retina_rays: usize, // rays cast across the view for the retina, 0 for none
+    pub obs_version: u32, // 1 for the original rows, 2 for egocentric ones, see world::being_row
+    pub b_speed: f32,
+    pub b_radius: f32,
+    pub o_radius: f32,
+    pub f_radius: f32,
+    pub s_radius: f32,
+
+    pub s_grow_rate: f32,
+    pub s_spatial_features: bool, // heard speechlets also tell where from, how loud, how old
+    pub s_speaker_features: bool, // heard speechlets also tell who spoke them, and if kin
+    pub s_self_hearing: bool,     // whether beings hear their own speechlets
+    pub s_acoustics: Acoustics,   // what obstructs do to the sound passing through them
+    pub s_wall_damping: f32,      // share of a sound that gets through each wall when damped
+    pub s_channel: SpeechChannel, // what beings say, their raw speech head or a token picked from it
+    pub s_vocab_size: usize,      // tokens to pick from on a discrete channel, up to SPEECHLET_LEN
+    pub s_gumbel_temperature: f32, // how much the speech head counts against the gumbel noise
+
+    pub b_death_energy: f32,
+    pub b_scatter_radius: f32,
+    pub b_scatter_count: usize,
+
+    pub base_ang_speed_degrees: f32,
+
+    pub b_start_energy: f32,
+    pub o_start_health: f32,
+    pub s_start_age: f32,
+    pub f_val: f32,
+    pub f_start_age: f32,
+
+    pub b_tire_rate: f32,
+    pub b_move_tire_rate: f32,
+    pub b_rot_tire_rate: f32,
+    pub o_age_rate: f32,
+    pub f_rot_rate: f32,
+    pub s_soften_rate: f32,
+
+    pub b_headon_damage: f32,
+    pub b_rear_damage: f32,
+    pub headon_b_hits_o_damage: f32,
+    pub spawn_o_ratio: f32, // fraction of start_energy spent to spawn obstruct
+    pub spawn_s_ratio: f32, // fraction of start_energy spent to speak
+    pub oob_penalty: f32,
+
+    pub low_energy_speed_damp_rate: f32, // beings slow down when their energy runs low
+    pub off_dir_movement_speed_damp_rate: f32, // beings slow down when not moving face-forward
+
+    pub n_food_spawn_per_step: usize,
+
+    pub max_food: usize, // starting value, lowered by max_food_reduction each generation
+    pub min_food: usize,
+    pub max_food_reduction: usize,
+}
+
+#[rustfmt::skip]
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig {
+            w_size:                             375,
+            n_cells:                            125,
+
+            b_start_count:                      50,
+            reworlding_threshold:               15,
+
+            b_fov:                              10,
+            b_view_half_angle_degrees:          180.,
+            b_view_range:                       0.,
+            b_blind_half_angle_degrees:         0.,
+            b_occlusion:                        Occlusion::Off,
+            b_retina_rays:                      0,
+            obs_version:                        1,
+            b_speed:                            0.5,
+            b_radius:                           2.75,
+            o_radius:                           2.,
+            f_radius:                           2.,
+            s_radius:                           1.5,
+
+            s_grow_rate:                        1.,
+            s_spatial_features:                 false,
+            s_speaker_features:                 false,
+            s_self_hearing:                     true,
+            s_acoustics:                        Acoustics::Off,
+            s_wall_damping:                     0.5,
+            s_channel:                          SpeechChannel::Continuous,
+            s_vocab_size:                       SPEECHLET_LEN,
+            s_gumbel_temperature:               1.,
+
+            b_death_energy:                     0.5,
+            b_scatter_radius:                   10.,
+            b_scatter_count:                    100,
+
+            base_ang_speed_degrees:             10.,
+
+            b_start_energy:                     10.,
+            o_start_health:                     25.,
+            s_start_age:                        5.,
+            f_val:                              2.,
+            f_start_age:                        50.,
+
+            b_tire_rate:                        0.005,
+            b_move_tire_rate:                   0.000,
+            b_rot_tire_rate:                    0.000,
+            o_age_rate:                         0.001,
+            f_rot_rate:                         50. / 1000.,
+            s_soften_rate:                      0.1,
+
+            b_headon_damage:                    0.25,
+            b_rear_damage:                      1.,
+            headon_b_hits_o_damage:             0.1,
+            spawn_o_ratio:                      0.01,
+            spawn_s_ratio:                      0.01,
+            oob_penalty:                        0.25,
+
+            low_energy_speed_damp_rate:         0.001,
+            off_dir_movement_speed_damp_rate:   0.001,
+
+            n_food_spawn_per_step:              1,
+
+            max_food:                           250,
+            min_food:                           25,
+            max_food_reduction:                 1,
+        }
+    }
+}
+
+// whether beings, foods and obstructs behind an obstruct are seen. flag keeps them in sight
+// with one more value on their row, 1 if hidden and 0 if not
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Occlusion {
+    Off,
+    Exclude,
+    Flag,
+}
+
+// whether obstructs between a speechlet's source and a hearer get in the way. damp scales what
+// is heard by s_wall_damping for every wall in between, block keeps it from being heard at all
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Acoustics {
+    Off,
+    Damp,
+    Block,
+}
+
+// how a being's speech head becomes a speechlet. continuous speaks the head as is. argmax and
+// gumbel pick one of the first s_vocab_size tokens, the largest value or a sample through gumbel
+// noise, and speak it one-hot, so hearers get the same SPEECHLET_LEN wide rows either way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeechChannel {
+    Continuous,
+    Argmax,
+    Gumbel,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(String),
+    UnsupportedFormat(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read config: {}", e),
+            ConfigError::Parse(e) => write!(f, "could not parse config: {}", e),
+            ConfigError::UnsupportedFormat(ext) => {
+                write!(f, "unsupported config format {:?}, use .toml or .json", ext)
+            }
+            ConfigError::Invalid(e) => write!(f, "invalid config: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl SimConfig {
+    // format is picked by extension, the result is validated before it is handed out
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path)?;
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let config: SimConfig = match ext.as_str() {
+            "toml" => toml::from_str(&text).map_err(|e| ConfigError::Parse(e.to_string()))?,
+            "json" => {
+                serde_json::from_str(&text).map_err(|e| ConfigError::Parse(e.to_string()))?
+            }
+            _ => return Err(ConfigError::UnsupportedFormat(ext)),
+        };
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+
+        if self.n_cells == 0 || self.w_size == 0 {
+            return invalid("w_size and n_cells must be non-zero".into());
+        }
+        if !self.w_size.is_multiple_of(self.n_cells) {
+            return invalid(format!(
+                "w_size ({}) must be a multiple of n_cells ({})",
+                self.w_size, self.n_cells
+            ));
+        }
+        if self.b_radius >= self.cell_size_float() {
+            return invalid(format!(
+                "b_radius ({}) must be smaller than the cell size ({})",
+                self.b_radius,
+                self.cell_size()
+            ));
+        }
+        // beings are placed at least b_radius and foods 1 in from every edge
+        let margin = self.b_radius.max(1.);
+        if self.w_size as f32 <= 2. * margin {
+            return invalid(format!(
+                "w_size ({}) must be larger than twice b_radius or 2, whichever is more",
+                self.w_size
+            ));
+        }
+        if self.b_scatter_radius <= 0. {
+            return invalid(format!(
+                "b_scatter_radius ({}) must be positive",
+                self.b_scatter_radius
+            ));
+        }
+        // every observation is scaled by b_fov_px, so a being must see at least its own cell
+        if self.b_fov < 1 {
+            return invalid(format!("b_fov ({}) must be at least 1", self.b_fov));
+        }
+        let half_angle = self.b_view_half_angle_degrees;
+        if half_angle <= 0. || half_angle > 180. {
+            return invalid(format!(
+                "b_view_half_angle_degrees ({}) must be in (0, 180]",
+                half_angle
+            ));
+        }
+        if !(0. ..180.).contains(&self.b_blind_half_angle_degrees) {
+            return invalid(format!(
+                "b_blind_half_angle_degrees ({}) must be in [0, 180)",
+                self.b_blind_half_angle_degrees
+            ));
+        }
+        if self.b_view_range < 0. {
+            return invalid(format!(
+                "b_view_range ({}) can not be negative",
+                self.b_view_range
+            ));
+        }
+        // only the disc of b_fov is searched, anything further would go unseen without a word
+        if self.b_view_range > self.b_fov as f32 {
+            return invalid(format!(
+                "b_view_range ({}) can not reach past b_fov ({})",
+                self.b_view_range, self.b_fov
+            ));
+        }
+        if !(0. ..=1.).contains(&self.s_wall_damping) {
+            return invalid(format!(
+                "s_wall_damping ({}) must be in [0, 1]",
+                self.s_wall_damping
+            ));
+        }
+        if !(1..=SPEECHLET_LEN).contains(&self.s_vocab_size) {
+            return invalid(format!(
+                "s_vocab_size ({}) must be between 1 and {}",
+                self.s_vocab_size, SPEECHLET_LEN
+            ));
+        }
+        if self.s_gumbel_temperature <= 0. {
+            return invalid(format!(
+                "s_gumbel_temperature ({}) must be positive",
+                self.s_gumbel_temperature
+            ));
+        }
+        if !(1..=2).contains(&self.obs_version) {
+            return invalid(format!("obs_version ({}) must be 1 or 2", self.obs_version));
+        }
+        if self.min_food > self.max_food {
+            return invalid(format!(
+                "min_food ({}) can not exceed max_food ({})",
+                self.min_food, self.max_food
+            ));
+        }
+        if self.b_start_energy <= 0. {
+            return invalid("b_start_energy must be positive".into());
+        }
+
+        Ok(())
+    }
+
+    pub fn cell_size(&self) -> usize {
+        self.w_size / self.n_cells
+    }
+
+    pub fn cell_size_float(&self) -> f32 {
+        self.cell_size() as f32
+    }
+
+    pub fn w_float(&self) -> f32 {
+        self.w_size as f32
+    }
+
+    pub fn b_fov_px(&self) -> f32 {
+        (self.b_fov as usize * self.cell_size()) as f32
+    }
+
+    pub fn b_view_range_px(&self) -> f32 {
+        self.b_view_range * self.cell_size_float()
+    }
+
+    // how far beings see, and how far retina rays reach
+    pub fn b_sight_px(&self) -> f32 {
+        if self.b_view_range > 0. {
+            self.b_view_range_px()
+        } else {
+            self.b_fov_px()
+        }
+    }
+
+    // whether beings see everything within b_fov, whichever way they face
+    pub fn b_sees_all_around(&self) -> bool {
+        self.b_view_half_angle_degrees >= 180.
+            && self.b_blind_half_angle_degrees == 0.
+            && self.b_view_range == 0.
+    }
+
+    pub fn input_widths(&self) -> InputWidths {
+        let hidden = usize::from(self.b_occlusion == Occlusion::Flag);
+        let (being, food_obstruct) = match self.obs_version {
+            1 => (3 + GENOME_LEN, 5),
+            _ => (6 + GENOME_LEN, 8),
+        };
+        InputWidths {
+            being: being + hidden,
+            food_obstruct: food_obstruct + hidden,
+            speechlet: SPEECHLET_LEN
+                + if self.s_spatial_features { 4 } else { 0 }
+                + if self.s_speaker_features { 4 } else { 0 },
+            retina: self.b_retina_rays * RETINA_CHANNELS,
+        }
+    }
+
+    // how many cells apart a being and whatever it bumps into, eats or hits can be, however
+    // far it sees
+    pub fn contact_reach(&self) -> isize {
+        let other = self.b_radius.max(self.o_radius).max(self.f_radius);
+        ((self.b_radius + other) / self.cell_size_float()).ceil() as isize
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use glam::Vec2;
+use serde::Serialize;
+
+use crate::consts::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    Eat,
+    Bump,
+    Oob,
+    Death,
+    Wall,
+    Speak,
+    Hear,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 7] = [
+        EventType::Eat,
+        EventType::Bump,
+        EventType::Oob,
+        EventType::Death,
+        EventType::Wall,
+        EventType::Speak,
+        EventType::Hear,
+    ];
+
+    // as it appears in the "type" field of a written event
+    pub fn name(self) -> &'static str {
+        match self {
+            EventType::Eat => "eat",
+            EventType::Bump => "bump",
+            EventType::Oob => "oob",
+            EventType::Death => "death",
+            EventType::Wall => "wall",
+            EventType::Speak => "speak",
+            EventType::Hear => "hear",
+        }
+    }
+}
+
+impl FromStr for EventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EventType::ALL
+            .into_iter()
+            .find(|t| t.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = EventType::ALL.iter().map(|t| t.name()).collect();
+                format!(
+                    "unknown event type {:?}, expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+// what happened, with whatever identifies the other party. ids are per generation like
+// being ids, so pair them with the event's generation
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    Eat {
+        food: usize,
+        at: Vec2,
+        val: f32,
+        flesh: bool,
+    },
+    // the being was pushed by other, rear means it was hit from behind rather than ramming
+    Bump {
+        other: usize,
+        rear: bool,
+        damage: f32,
+    },
+    Oob {
+        penalty: f32,
+    },
+    Death {
+        energy: f32,
+    },
+    Wall {
+        at: Vec2,
+    },
+    // token is what was picked on a discrete channel, left out on the continuous one
+    Speak {
+        speechlet: [f32; SPEECHLET_LEN],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        token: Option<usize>,
+    },
+    Hear {
+        speechlet: [f32; SPEECHLET_LEN],
+        from: Vec2,
+        speaker: usize,
+    },
+}
+
+impl EventKind {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            EventKind::Eat { .. } => EventType::Eat,
+            EventKind::Bump { .. } => EventType::Bump,
+            EventKind::Oob { .. } => EventType::Oob,
+            EventKind::Death { .. } => EventType::Death,
+            EventKind::Wall { .. } => EventType::Wall,
+            EventKind::Speak { .. } => EventType::Speak,
+            EventKind::Hear { .. } => EventType::Hear,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub generation: usize,
+    pub age: usize,
+    pub being: usize,
+    pub pos: Vec2, // the being's for every kind, eat, wall and hear say where the other thing was
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+// events of the wanted types, buffered by the world until the caller takes them. wants
+// nothing by default, so a world nobody listens to only pays for a check per interaction
+#[derive(Default)]
+pub struct EventLog {
+    wanted: Vec<EventType>,
+    events: Vec<Event>,
+    generation: usize,
+    age: usize,
+}
+
+impl EventLog {
+    pub fn want(&mut self, types: &[EventType]) {
+        self.wanted = types.to_vec();
+    }
+
+    pub fn wants(&self, t: EventType) -> bool {
+        self.wanted.contains(&t)
+    }
+
+    // stamps the events recorded from now on
+    pub fn at(&mut self, generation: usize, age: usize) {
+        self.generation = generation;
+        self.age = age;
+    }
+
+    pub fn record(&mut self, being: usize, pos: Vec2, kind: EventKind) {
+        if self.wants(kind.event_type()) {
+            self.events.push(Event {
+                generation: self.generation,
+                age: self.age,
+                being,
+                pos,
+                kind,
+            });
+        }
+    }
+
+    pub fn take(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+// events as json lines
+pub struct EventWriter {
+    out: BufWriter<File>,
+}
+
+impl EventWriter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(EventWriter {
+            out: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub fn write(&mut self, events: &[Event]) -> io::Result<()> {
+        for e in events {
+            serde_json::to_writer(&mut self.out, e)?;
+            writeln!(self.out)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
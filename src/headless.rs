@@ -0,0 +1,178 @@
+use std::{
+    path::PathBuf,
+    process::ExitCode,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use crate::{
+    brain::Brain, checkpoint::CheckpointPolicy, events::EventWriter, stats::StatsLog, World,
+};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// makes a running headless run stop at the next tick as if a limit was reached, so its
+// snapshot and checkpoint still get written. meant to be called from a ctrl-c handler
+pub fn request_stop() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+pub(crate) fn stop_requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+// when a headless run stops, whichever limit is hit first
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunLimits {
+    pub generations: Option<usize>,
+    pub ticks: Option<usize>,
+}
+
+impl RunLimits {
+    fn reached(&self, generations: usize, ticks: usize) -> bool {
+        self.generations.is_some_and(|g| generations >= g) || self.ticks.is_some_and(|t| ticks >= t)
+    }
+}
+
+// world snapshot rewritten every few ticks and once more when the run ends
+#[derive(Debug, Clone)]
+pub struct SnapshotSchedule {
+    pub path: PathBuf,
+    pub every: usize,
+}
+
+fn checkpoint<const D: usize, M: Brain>(world: &World<D, M>, policy: &CheckpointPolicy) -> bool {
+    // nothing to resume from before the first generation has ended
+    if world.last_survivors.is_empty() {
+        return true;
+    }
+    match policy.write(world) {
+        Ok(path) => {
+            println!("checkpoint: {}", path.display());
+            true
+        }
+        Err(e) => {
+            eprintln!("{}: {}", policy.dir.display(), e);
+            false
+        }
+    }
+}
+
+// everything a headless run writes besides stdout, each part optional
+#[derive(Default)]
+pub struct RunOutputs {
+    pub snapshots: Option<SnapshotSchedule>,
+    pub checkpoints: Option<CheckpointPolicy>,
+    pub stats: Option<StatsLog>,
+    pub events: Option<EventWriter>, // the world must be told which types to record
+}
+
+fn save<const D: usize, M: Brain>(world: &mut World<D, M>, schedule: &SnapshotSchedule) -> bool {
+    match world.save_snapshot(&schedule.path) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("{}: {}", schedule.path.display(), e);
+            false
+        }
+    }
+}
+
+// steps the world without a window until a limit is reached or ctrl-c, printing every
+// generation summary. exits with failure if the population dies out with no survivors left to
+// rebreed from
+pub fn run_headless<const D: usize, M: Brain>(
+    world: &mut World<D, M>,
+    limits: RunLimits,
+    mut outputs: RunOutputs,
+) -> ExitCode {
+    if limits.generations.is_none() && limits.ticks.is_none() {
+        eprintln!("a headless run needs a generation or tick limit");
+        return ExitCode::from(2);
+    }
+
+    let start = Instant::now();
+    let (mut generations, mut ticks) = (0, 0);
+
+    let mut interrupted = false;
+    while !limits.reached(generations, ticks) {
+        if stop_requested() {
+            interrupted = true;
+            break;
+        }
+
+        let summary = world.step(1);
+        ticks += 1;
+
+        if let Some(writer) = &mut outputs.events {
+            if let Err(e) = writer.write(&world.take_events()) {
+                eprintln!("could not write events: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+
+        if let Some(summary) = summary {
+            println!("{}", summary);
+            generations += 1;
+
+            if let Some(log) = &mut outputs.stats {
+                if let Err(e) = log.write(&summary) {
+                    eprintln!("could not write stats: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+
+            if world.beings_and_models.is_empty() {
+                eprintln!("population extinct with no survivors to rebreed, stopping");
+                return ExitCode::FAILURE;
+            }
+
+            if let Some(policy) = &outputs.checkpoints {
+                if policy.due(world.generation) && !checkpoint(world, policy) {
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+
+        if let Some(schedule) = &outputs.snapshots {
+            if ticks % schedule.every == 0 && !save(world, schedule) {
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if let Some(schedule) = &outputs.snapshots {
+        if !save(world, schedule) {
+            return ExitCode::FAILURE;
+        }
+    }
+    if let Some(writer) = &mut outputs.events {
+        if let Err(e) = writer.flush() {
+            eprintln!("could not write events: {}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+    if interrupted {
+        if let Some(policy) = &outputs.checkpoints {
+            if !checkpoint(world, policy) {
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let secs = start.elapsed().as_secs_f32();
+    println!(
+        "done: {} generations, {} ticks in {:.1}s ({:.1} ticks/s), world at generation {} age {}",
+        generations,
+        ticks,
+        secs,
+        ticks as f32 / secs,
+        world.generation,
+        world.age,
+    );
+
+    if interrupted {
+        ExitCode::from(130)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
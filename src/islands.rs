@@ -0,0 +1,166 @@
+// several worlds evolving side by side, one thread each, with copies of every island's best
+// survivors sent to the next island around a ring every few generations. islands only meet at
+// those migrations, so a run repeats exactly whichever order the threads happen to run in
+
+use std::{fmt, process::ExitCode, thread};
+
+use serde::Serialize;
+
+use crate::{brain::Brain, headless::stop_requested, stats::StatsLog, GenerationSummary, World};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub every: usize,    // generations between migrations
+    pub migrants: usize, // survivors each island sends on
+}
+
+// one island's run, reported once all islands stop
+#[derive(Debug, Clone, Serialize)]
+pub struct IslandReport {
+    pub island: usize,
+    pub seed: u64,
+    pub generations: usize,
+    pub immigrants: usize,
+    pub best_mean_energy: f32,
+    pub best_max_energy: f32,
+    pub food_eaten: usize,
+    pub survivors: usize, // in the last generation
+}
+
+impl IslandReport {
+    fn new(island: usize, seed: u64) -> Self {
+        IslandReport {
+            island,
+            seed,
+            generations: 0,
+            immigrants: 0,
+            best_mean_energy: 0.,
+            best_max_energy: 0.,
+            food_eaten: 0,
+            survivors: 0,
+        }
+    }
+
+    fn record(&mut self, summary: &GenerationSummary) {
+        self.generations += 1;
+        self.best_mean_energy = self.best_mean_energy.max(summary.mean_energy);
+        self.best_max_energy = self.best_max_energy.max(summary.max_energy);
+        self.food_eaten += summary.stats.food_eaten;
+        self.survivors = summary.survivors;
+    }
+}
+
+impl fmt::Display for IslandReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "island {} (seed {}): {} generations, {} immigrants, best mean energy {:.2}, \
+             best max energy {:.2}, {} food eaten, {} survivors last generation",
+            self.island,
+            self.seed,
+            self.generations,
+            self.immigrants,
+            self.best_mean_energy,
+            self.best_max_energy,
+            self.food_eaten,
+            self.survivors
+        )
+    }
+}
+
+// steps until n more generations have ended, ctrl-c, or the island has died out. an island
+// with no beings stays empty until migrants arrive
+fn evolve<const D: usize, M: Brain>(world: &mut World<D, M>, n: usize) -> Vec<GenerationSummary> {
+    let mut summaries = vec![];
+    while summaries.len() < n && !stop_requested() && !world.beings_and_models.is_empty() {
+        if let Some(summary) = world.step(1) {
+            summaries.push(summary);
+        }
+    }
+    summaries
+}
+
+// every island's top survivors go to the next island, all picked before any arrive
+fn migrate<const D: usize, M: Brain>(
+    worlds: &mut [World<D, M>],
+    migrants: usize,
+    reports: &mut [IslandReport],
+) {
+    if worlds.len() < 2 || migrants == 0 {
+        return;
+    }
+    let emigrants: Vec<Vec<M>> = worlds.iter().map(|w| w.top_survivors(migrants)).collect();
+    for (i, group) in emigrants.into_iter().enumerate() {
+        let to = (i + 1) % worlds.len();
+        reports[to].immigrants += group.len();
+        worlds[to].immigrate(group);
+    }
+}
+
+// evolves every world for the given number of generations, printing their summaries in island
+// order and writing them to the island's stats log if there is one per island. exits with
+// failure once every island has died out
+pub fn run_islands<const D: usize, M: Brain + Send>(
+    worlds: &mut [World<D, M>],
+    generations: usize,
+    migration: Migration,
+    mut stats: Vec<StatsLog>,
+) -> ExitCode {
+    let mut reports: Vec<IslandReport> = worlds
+        .iter()
+        .enumerate()
+        .map(|(i, w)| IslandReport::new(i, w.seed))
+        .collect();
+
+    let mut done = 0;
+    let mut interrupted = false;
+    while done < generations {
+        let epoch = migration.every.min(generations - done);
+        let results: Vec<Vec<GenerationSummary>> = thread::scope(|s| {
+            let handles: Vec<_> = worlds
+                .iter_mut()
+                .map(|w| s.spawn(move || evolve(w, epoch)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("island thread panicked"))
+                .collect()
+        });
+        done += epoch;
+
+        for (i, summaries) in results.iter().enumerate() {
+            for summary in summaries {
+                println!("island {}: {}", i, summary);
+                reports[i].record(summary);
+                if let Some(log) = stats.get_mut(i) {
+                    if let Err(e) = log.write(summary) {
+                        eprintln!("could not write stats of island {}: {}", i, e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+        }
+
+        if stop_requested() {
+            interrupted = true;
+            break;
+        }
+        if done < generations {
+            migrate(worlds, migration.migrants, &mut reports);
+        }
+        if worlds.iter().all(|w| w.beings_and_models.is_empty()) {
+            eprintln!("every island died out with no survivors to rebreed, stopping");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    for report in &reports {
+        println!("{}", report);
+    }
+
+    if interrupted {
+        ExitCode::from(130)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,38 @@
+// neural beings that eat, build walls and speak. the simulation, brains and evolution live
+// here, the samsarsa binary and its ggez viewer are thin layers on top
+
+pub mod baselines;
+pub mod being_nn;
+pub mod bench;
+pub mod brain;
+pub mod checkpoint;
+pub mod config;
+pub mod events;
+pub mod headless;
+pub mod islands;
+pub mod retina_nn;
+pub mod snapshot;
+pub mod stats;
+#[cfg(feature = "viewer")]
+pub mod viewer;
+pub mod world;
+
+#[rustfmt::skip]
+pub mod consts {
+    use burn::backend;
+
+    // array lengths stay compile-time, every other tunable lives in config::SimConfig
+    pub const GENOME_LEN:                             usize = 10;                  // future prospect
+
+    pub const SPEECHLET_LEN:                          usize = 8;                   // length of the sound vector a being can emit
+    pub const B_OUTPUT_LEN:                           usize = 4 + SPEECHLET_LEN;   // (f-b, rotate, spawn obstruct, spawn_speechlet, *speechlet)
+    pub const RETINA_CHANNELS:                        usize = 4;                   // per ray: distance, then being, food and obstruct brightness
+    
+    pub type WorldRng                                       = rand_chacha::ChaCha8Rng;
+    pub type BACKEND                                        = backend::NdArray;
+    pub const DEVICE:       backend::ndarray::NdArrayDevice = backend::ndarray::NdArrayDevice::Cpu;
+}
+
+pub use config::SimConfig;
+pub use consts::*;
+pub use world::{Being, Food, GenerationSummary, Obstruct, Speechlet, World};
@@ -0,0 +1,260 @@
+// a brain that sees through the retina, one fixed-size vector of ray casts, rather than the sets
+// of beings, foods and obstructs SumFxModel pools over. it hears and feels itself the same way
+
+use burn::nn::Lstm;
+use burn::prelude::*;
+use burn::tensor::{activation, backend::Backend, Tensor};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    being_nn::{
+        combine_lstms, create_ff, random_lstm, tensor_values, tensorize_rows, Activation, FFState,
+        LstmState, Tanh, FF,
+    },
+    brain::{Brain, InputWidths, Observation},
+    consts::*,
+};
+
+// retina, speechlet and self outputs, concatenated
+const RETINA_OUT: usize = 16;
+const SPEECHLET_OUT: usize = 8;
+const SELF_OUT: usize = 8;
+const HIDDEN: usize = RETINA_OUT + SPEECHLET_OUT + SELF_OUT;
+
+#[derive(Clone)]
+pub struct RetinaModel<B: Backend> {
+    pub retina_model: FF<B>,
+    pub speechlet_model: FF<B>,
+    pub self_model: FF<B>,
+
+    pub lstm: Lstm<B>,
+    pub final_model: FF<B>,
+
+    state: (Tensor<B, 2>, Tensor<B, 2>),
+}
+
+fn tanh_ff<B: Backend>(d_in: usize, d_out: usize, rng: &mut impl Rng, device: &Device<B>) -> FF<B> {
+    let acts = vec![Activation::Tanh(Tanh {}), Activation::Tanh(Tanh {})];
+    create_ff(vec![d_in, d_out], acts, rng, device)
+}
+
+fn blank_state<B: Backend>(device: &Device<B>) -> (Tensor<B, 2>, Tensor<B, 2>) {
+    (
+        Tensor::<B, 2>::zeros([1, HIDDEN], device),
+        Tensor::<B, 2>::zeros([1, HIDDEN], device),
+    )
+}
+
+impl<B: Backend> RetinaModel<B> {
+    pub fn standard_model(widths: InputWidths, rng: &mut impl Rng, device: &Device<B>) -> Self {
+        RetinaModel {
+            retina_model: tanh_ff(widths.retina, RETINA_OUT, rng, device),
+            speechlet_model: tanh_ff(widths.speechlet, SPEECHLET_OUT, rng, device),
+            self_model: tanh_ff(5, SELF_OUT, rng, device),
+            lstm: random_lstm(HIDDEN, HIDDEN, rng, device),
+            final_model: tanh_ff(HIDDEN, B_OUTPUT_LEN, rng, device),
+            state: blank_state(device),
+        }
+    }
+
+    pub fn forward(
+        &mut self,
+        retina_tensor: Tensor<B, 2>,
+        speechlet_tensor: Tensor<B, 2>,
+        self_tensor: Tensor<B, 2>,
+    ) -> Tensor<B, 1> {
+        let intermediate = Tensor::cat(
+            vec![
+                self.retina_model.forward(retina_tensor),
+                self.speechlet_model.forward(speechlet_tensor).mean_dim(0),
+                self.self_model.forward(self_tensor),
+            ],
+            1,
+        );
+
+        let (c, h) = self
+            .lstm
+            .forward(intermediate.unsqueeze(), Some(self.state.clone()));
+        let (c, h): (Tensor<B, 2>, Tensor<B, 2>) = (c.squeeze(0).no_grad(), h.squeeze(0).no_grad());
+        self.state = (c, h.clone());
+
+        activation::tanh(self.final_model.forward(h).squeeze(0))
+    }
+
+    pub fn crossover(self, other: RetinaModel<B>, weight: f32, device: &Device<B>) -> Self {
+        RetinaModel {
+            retina_model: self.retina_model.crossover(other.retina_model, weight),
+            speechlet_model: self
+                .speechlet_model
+                .crossover(other.speechlet_model, weight),
+            self_model: self.self_model.crossover(other.self_model, weight),
+            final_model: self.final_model.crossover(other.final_model, weight),
+            lstm: combine_lstms(self.lstm, other.lstm, weight, 1. - weight),
+            state: blank_state(device),
+        }
+    }
+
+    pub fn mutate(self, rate: f32, rng: &mut impl Rng, device: &Device<B>) -> Self {
+        let retina_model = self.retina_model.mutate(rate, rng, device);
+        let speechlet_model = self.speechlet_model.mutate(rate, rng, device);
+        let self_model = self.self_model.mutate(rate, rng, device);
+        let final_model = self.final_model.mutate(rate, rng, device);
+        let mutation_lstm = random_lstm(HIDDEN, HIDDEN, rng, device);
+
+        RetinaModel {
+            retina_model,
+            speechlet_model,
+            self_model,
+            final_model,
+            lstm: combine_lstms(self.lstm, mutation_lstm, 1., rate),
+            state: blank_state(device),
+        }
+    }
+
+    pub fn to_state(&self) -> RetinaModelState {
+        RetinaModelState {
+            retina_model: FFState::from_ff(&self.retina_model),
+            speechlet_model: FFState::from_ff(&self.speechlet_model),
+            self_model: FFState::from_ff(&self.self_model),
+
+            lstm: LstmState::from_lstm(&self.lstm),
+            final_model: FFState::from_ff(&self.final_model),
+
+            state: (
+                tensor_values(self.state.0.clone()),
+                tensor_values(self.state.1.clone()),
+            ),
+        }
+    }
+
+    pub fn from_state(state: &RetinaModelState, device: &Device<B>) -> Result<Self, String> {
+        let (c, h) = &state.state;
+        if c.len() != HIDDEN || h.len() != HIDDEN {
+            return Err(format!("lstm state must be {} wide", HIDDEN));
+        }
+
+        Ok(RetinaModel {
+            retina_model: state.retina_model.to_ff(device)?,
+            speechlet_model: state.speechlet_model.to_ff(device)?,
+            self_model: state.self_model.to_ff(device)?,
+
+            lstm: state.lstm.to_lstm(device)?,
+            final_model: state.final_model.to_ff(device)?,
+
+            state: (
+                Tensor::<B, 1>::from_floats(c.as_slice(), device).reshape([1, HIDDEN]),
+                Tensor::<B, 1>::from_floats(h.as_slice(), device).reshape([1, HIDDEN]),
+            ),
+        })
+    }
+}
+
+impl Brain for RetinaModel<BACKEND> {
+    type State = RetinaModelState;
+
+    fn random(rng: &mut WorldRng, widths: InputWidths) -> Self {
+        RetinaModel::standard_model(widths, rng, &DEVICE)
+    }
+
+    fn check_widths(widths: InputWidths) -> Result<(), String> {
+        if widths.retina == 0 {
+            return Err("the world casts no retina rays, set b_retina_rays".into());
+        }
+        Ok(())
+    }
+
+    fn act(&mut self, obs: &Observation) -> [f32; B_OUTPUT_LEN] {
+        let retina_tensor = Tensor::<BACKEND, 1>::from_floats(obs.retina, &DEVICE)
+            .reshape([1, obs.retina.len()])
+            .no_grad();
+        let speechlet_width = self.speechlet_model.lins[0].weight.shape().dims[0];
+        let speechlet_tensor = tensorize_rows(obs.speechlets, speechlet_width);
+        let self_tensor = Tensor::<BACKEND, 1>::from_floats(obs.own.as_slice(), &DEVICE)
+            .reshape([1, 5])
+            .no_grad();
+
+        let model_output = self
+            .forward(retina_tensor, speechlet_tensor, self_tensor)
+            .into_data()
+            .value;
+
+        let mut output = [0.; B_OUTPUT_LEN];
+        output.copy_from_slice(&model_output[..B_OUTPUT_LEN]);
+        output
+    }
+
+    fn reset(&mut self) {
+        self.state = blank_state(&DEVICE);
+    }
+
+    fn crossover(self, other: Self, weight: f32) -> Self {
+        RetinaModel::crossover(self, other, weight, &DEVICE)
+    }
+
+    fn mutate(self, rate: f32, rng: &mut WorldRng) -> Self {
+        RetinaModel::mutate(self, rate, rng, &DEVICE)
+    }
+
+    fn to_state(&self) -> RetinaModelState {
+        RetinaModel::to_state(self)
+    }
+
+    fn from_state(state: &RetinaModelState, widths: InputWidths) -> Result<Self, String> {
+        state.check_layout(widths)?;
+        RetinaModel::from_state(state, &DEVICE)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetinaModelState {
+    pub retina_model: FFState,
+    pub speechlet_model: FFState,
+    pub self_model: FFState,
+
+    pub lstm: LstmState,
+    pub final_model: FFState,
+
+    pub state: (Vec<f32>, Vec<f32>), // lstm (cell, hidden) carried between steps
+}
+
+impl RetinaModelState {
+    // the layers must fit each other and the world's retina, which a world casting no rays
+    // does not have
+    pub fn check_layout(&self, widths: InputWidths) -> Result<(), String> {
+        RetinaModel::check_widths(widths)?;
+
+        let mut sensory_out = 0;
+        for (name, ff, d_in) in [
+            ("retina_model", &self.retina_model, widths.retina),
+            ("speechlet_model", &self.speechlet_model, widths.speechlet),
+            ("self_model", &self.self_model, 5),
+        ] {
+            let (i, o) = ff.dims(name)?;
+            if i != d_in {
+                return Err(format!(
+                    "{} takes {} inputs, the world gives {}",
+                    name, i, d_in
+                ));
+            }
+            sensory_out += o;
+        }
+
+        if sensory_out != HIDDEN || self.lstm.d_input != HIDDEN || self.lstm.d_hidden != HIDDEN {
+            return Err(format!(
+                "sensory models output {} into a {}x{} lstm, expected {}",
+                sensory_out, self.lstm.d_input, self.lstm.d_hidden, HIDDEN
+            ));
+        }
+
+        let (i, o) = self.final_model.dims("final_model")?;
+        if i != HIDDEN || o != B_OUTPUT_LEN {
+            return Err(format!(
+                "final_model is {}->{}, expected {}->{}",
+                i, o, HIDDEN, B_OUTPUT_LEN
+            ));
+        }
+
+        Ok(())
+    }
+}
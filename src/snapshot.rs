@@ -0,0 +1,334 @@
+use std::{fmt, fs, io, path::Path};
+
+use rand::SeedableRng;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use slotmap::{DefaultKey, Key, SlotMap};
+
+use crate::{
+    brain::{Brain, InputWidths},
+    config::SimConfig,
+    consts::*,
+    stats::GenerationStats,
+    Being, Food, Obstruct, Speechlet, World,
+};
+
+// bumped whenever the layout below changes, old files are refused rather than misread
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    Version { found: u32, expected: u32 },
+    Invalid(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot io failed: {}", e),
+            SnapshotError::Format(e) => write!(f, "malformed snapshot: {}", e),
+            SnapshotError::Version { found, expected } => write!(
+                f,
+                "snapshot version {} can not be loaded, expected {}",
+                found, expected
+            ),
+            SnapshotError::Invalid(e) => write!(f, "invalid snapshot: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        SnapshotError::Format(e)
+    }
+}
+
+// a slotmap's iteration order follows slot indices and new entries reuse freed slots in
+// free-list order, both of which steer the simulation. so the snapshot keeps every slot
+// (vacant ones as None) plus the free list, and rebuilds the map with the same layout
+#[derive(Serialize, Deserialize)]
+struct SlotLayout<S> {
+    slots: Vec<Option<S>>, // slot 1 onwards, slot 0 is slotmap's sentinel
+    free: Vec<u32>,
+}
+
+fn slot_index(k: DefaultKey) -> u32 {
+    k.data().as_ffi() as u32
+}
+
+fn slot_version(k: DefaultKey) -> u32 {
+    (k.data().as_ffi() >> 32) as u32
+}
+
+// slotmap does not expose its free list, so it is read by inserting placeholders until a
+// brand new slot (version 1) gets appended, then removing them again in reverse. that leaves
+// the same free order behind, with the appended slot standing in for "push at the end".
+// returns the free list and the index of that appended slot
+fn probe_free_list<V>(
+    map: &mut SlotMap<DefaultKey, V>,
+    placeholder: impl Fn() -> V,
+) -> (Vec<u32>, u32) {
+    let mut probes = vec![];
+    loop {
+        let k = map.insert(placeholder());
+        probes.push(k);
+        if slot_version(k) == 1 {
+            break;
+        }
+    }
+
+    for k in probes.iter().rev() {
+        map.remove(*k);
+    }
+
+    let appended = probes.pop().unwrap();
+    (probes.into_iter().map(slot_index).collect(), slot_index(appended))
+}
+
+fn capture<V, S>(
+    map: &mut SlotMap<DefaultKey, V>,
+    placeholder: impl Fn() -> V,
+    to_state: impl Fn(&V) -> S,
+) -> SlotLayout<S> {
+    let (free, appended) = probe_free_list(map, placeholder);
+
+    let mut slots: Vec<Option<S>> = (1..appended).map(|_| None).collect();
+    for (k, v) in map.iter() {
+        slots[slot_index(k) as usize - 1] = Some(to_state(v));
+    }
+
+    SlotLayout { slots, free }
+}
+
+// key each stored slot index ended up under, None for vacant slots
+type SlotKeys = Vec<Option<DefaultKey>>;
+
+fn restore<V, S>(
+    layout: SlotLayout<S>,
+    placeholder: impl Fn() -> V,
+    from_state: impl Fn(S) -> Result<V, SnapshotError>,
+) -> Result<(SlotMap<DefaultKey, V>, SlotKeys), SnapshotError> {
+    let mut map = SlotMap::new();
+    let mut keys = vec![None];
+    let mut placeholders = vec![None];
+
+    // fill slots in index order, vacant ones with placeholders, plus the trailing appended slot
+    for slot in layout.slots.into_iter().chain([None]) {
+        match slot {
+            Some(state) => {
+                keys.push(Some(map.insert(from_state(state)?)));
+                placeholders.push(None);
+            }
+            None => {
+                placeholders.push(Some(map.insert(placeholder())));
+                keys.push(None);
+            }
+        }
+    }
+
+    // free the placeholders so the free list reads free[0], free[1], .., appended slot
+    let appended = placeholders.len() as u32 - 1;
+    for idx in layout.free.iter().chain([&appended]).rev() {
+        let k = placeholders
+            .get_mut(*idx as usize)
+            .and_then(Option::take)
+            .ok_or_else(|| SnapshotError::Invalid(format!("bad free slot {}", idx)))?;
+        map.remove(k);
+    }
+    if placeholders.iter().any(Option::is_some) {
+        return Err(SnapshotError::Invalid("vacant slot missing from free list".into()));
+    }
+
+    Ok((map, keys))
+}
+
+fn cells_to_indices(cells: &[Vec<DefaultKey>]) -> Vec<Vec<u32>> {
+    cells
+        .iter()
+        .map(|c| c.iter().map(|k| slot_index(*k)).collect())
+        .collect()
+}
+
+fn indices_to_cells(
+    cells: Vec<Vec<u32>>,
+    keys: &[Option<DefaultKey>],
+) -> Result<Vec<Vec<DefaultKey>>, SnapshotError> {
+    cells
+        .into_iter()
+        .map(|c| {
+            c.into_iter()
+                .map(|i| {
+                    keys.get(i as usize).copied().flatten().ok_or_else(|| {
+                        SnapshotError::Invalid(format!("cell refers to empty slot {}", i))
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "S: Serialize + DeserializeOwned")]
+struct BeingEntry<S> {
+    being: Being,
+    model: S,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "S: Serialize + DeserializeOwned")]
+struct WorldSnapshot<S> {
+    version: u32,
+
+    config: SimConfig,
+    seed: u64,
+    rng: WorldRng,
+
+    beings: SlotLayout<BeingEntry<S>>,
+    obstructs: SlotLayout<Obstruct>,
+    foods: SlotLayout<Food>,
+    speechlets: SlotLayout<Speechlet>,
+
+    being_cells: Vec<Vec<u32>>,
+    obstruct_cells: Vec<Vec<u32>>,
+    food_cells: Vec<Vec<u32>>,
+    speechlet_cells: Vec<Vec<u32>>,
+
+    being_id: usize,
+    ob_id: usize,
+    food_id: usize,
+
+    age: usize,
+    generation: usize,
+    max_food: usize,
+    last_survivors: Vec<S>,
+    #[serde(default)]
+    last_survivor_energies: Vec<f32>,
+    #[serde(default)]
+    last_survivor_clans: Vec<usize>,
+    #[serde(default)]
+    stats: GenerationStats,
+}
+
+fn placeholder_model<M: Brain>(widths: InputWidths) -> M {
+    M::random(&mut WorldRng::seed_from_u64(0), widths)
+}
+
+fn load_model<M: Brain>(state: &M::State, widths: InputWidths) -> Result<M, SnapshotError> {
+    M::from_state(state, widths).map_err(SnapshotError::Invalid)
+}
+
+impl<const D: usize, M: Brain> World<D, M> {
+    // mutable only because reading the slotmaps' free lists means briefly inserting into them,
+    // the world is left exactly as it was
+    pub fn save_snapshot(&mut self, path: &Path) -> Result<(), SnapshotError> {
+        let placeholder: M = placeholder_model(self.config.input_widths());
+
+        let snapshot = WorldSnapshot {
+            version: SNAPSHOT_VERSION,
+
+            config: self.config.clone(),
+            seed: self.seed,
+            rng: self.rng.clone(),
+
+            beings: capture(
+                &mut self.beings_and_models,
+                || (Being::default(), placeholder.clone()),
+                |(b, m)| BeingEntry {
+                    being: b.clone(),
+                    model: m.to_state(),
+                },
+            ),
+            obstructs: capture(&mut self.obstructs, Obstruct::default, Obstruct::clone),
+            foods: capture(&mut self.foods, Food::default, Food::clone),
+            speechlets: capture(&mut self.speechlets, Speechlet::default, Speechlet::clone),
+
+            being_cells: cells_to_indices(&self.being_cells),
+            obstruct_cells: cells_to_indices(&self.obstruct_cells),
+            food_cells: cells_to_indices(&self.food_cells),
+            speechlet_cells: cells_to_indices(&self.speechlet_cells),
+
+            being_id: self.being_id,
+            ob_id: self.ob_id,
+            food_id: self.food_id,
+
+            age: self.age,
+            generation: self.generation,
+            max_food: self.max_food,
+            last_survivors: self.last_survivors.iter().map(|m| m.to_state()).collect(),
+            last_survivor_energies: self.last_survivor_energies.clone(),
+            last_survivor_clans: self.last_survivor_clans.clone(),
+            stats: self.stats,
+        };
+
+        // written next to the target first so a crash mid-write never clobbers the last good file
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&snapshot)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn load_snapshot(path: &Path) -> Result<Self, SnapshotError> {
+        let snapshot: WorldSnapshot<M::State> = serde_json::from_slice(&fs::read(path)?)?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version {
+                found: snapshot.version,
+                expected: SNAPSHOT_VERSION,
+            });
+        }
+        snapshot
+            .config
+            .validate()
+            .map_err(|e| SnapshotError::Invalid(e.to_string()))?;
+
+        let widths = snapshot.config.input_widths();
+        let mut world = World::new(snapshot.config, snapshot.seed);
+        world.rng = snapshot.rng;
+
+        let placeholder: M = placeholder_model(widths);
+        let (beings_and_models, being_keys) = restore(
+            snapshot.beings,
+            || (Being::default(), placeholder.clone()),
+            |e| Ok((e.being, load_model(&e.model, widths)?)),
+        )?;
+        let (obstructs, obstruct_keys) = restore(snapshot.obstructs, Obstruct::default, Ok)?;
+        let (foods, food_keys) = restore(snapshot.foods, Food::default, Ok)?;
+        let (speechlets, speechlet_keys) = restore(snapshot.speechlets, Speechlet::default, Ok)?;
+
+        world.beings_and_models = beings_and_models;
+        world.obstructs = obstructs;
+        world.foods = foods;
+        world.speechlets = speechlets;
+
+        world.being_cells = indices_to_cells(snapshot.being_cells, &being_keys)?;
+        world.obstruct_cells = indices_to_cells(snapshot.obstruct_cells, &obstruct_keys)?;
+        world.food_cells = indices_to_cells(snapshot.food_cells, &food_keys)?;
+        world.speechlet_cells = indices_to_cells(snapshot.speechlet_cells, &speechlet_keys)?;
+
+        world.being_id = snapshot.being_id;
+        world.ob_id = snapshot.ob_id;
+        world.food_id = snapshot.food_id;
+
+        world.age = snapshot.age;
+        world.generation = snapshot.generation;
+        world.max_food = snapshot.max_food;
+        world.last_survivors = snapshot
+            .last_survivors
+            .iter()
+            .map(|s| load_model(s, widths))
+            .collect::<Result<_, _>>()?;
+        world.last_survivor_energies = snapshot.last_survivor_energies;
+        world.last_survivor_clans = snapshot.last_survivor_clans;
+        world.stats = snapshot.stats;
+
+        Ok(world)
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{consts::*, GenerationSummary};
+
+// what happened over one generation, counted as it happens and reset by every reworld
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationStats {
+    pub food_eaten: usize,
+    pub obstructs_spawned: usize,
+    pub speechlets_emitted: usize,
+    pub being_collisions: usize, // per being involved and per substep, so a pair bumping once is 2
+    pub oob_penalties: usize,
+    pub deaths: usize,
+    #[serde(default)]
+    pub tokens_spoken: [usize; SPEECHLET_LEN], // per token, only on a discrete speech channel
+}
+
+impl GenerationStats {
+    // how many different tokens were spoken
+    pub fn vocab_used(&self) -> usize {
+        self.tokens_spoken.iter().filter(|n| **n > 0).count()
+    }
+
+    // shannon entropy of the spoken tokens in bits, 0 when none or one token was spoken
+    pub fn vocab_entropy(&self) -> f32 {
+        let total = self.tokens_spoken.iter().sum::<usize>() as f32;
+        self.tokens_spoken
+            .iter()
+            .filter(|n| **n > 0)
+            .fold(0., |h, n| {
+                let p = *n as f32 / total;
+                h - p * p.log2()
+            })
+    }
+}
+
+const CSV_HEADER: &str = "generation,age,survivors,mean_energy,max_energy,food_eaten,\
+    obstructs_spawned,speechlets_emitted,being_collisions,oob_penalties,deaths,max_food,\
+    vocab_used,vocab_entropy";
+
+enum Format {
+    Csv,
+    Jsonl,
+}
+
+// one line per generation summary, as csv or json lines depending on the file extension
+pub struct StatsLog {
+    out: BufWriter<File>,
+    format: Format,
+}
+
+impl StatsLog {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let format = match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => Format::Csv,
+            Some("jsonl") => Format::Jsonl,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "stats file must end in .csv or .jsonl",
+                ))
+            }
+        };
+
+        let mut out = BufWriter::new(File::create(path)?);
+        if let Format::Csv = format {
+            writeln!(out, "{}", CSV_HEADER)?;
+        }
+        Ok(StatsLog { out, format })
+    }
+
+    // flushed every line, so a log being plotted while the run goes on is never half a record
+    pub fn write(&mut self, summary: &GenerationSummary) -> io::Result<()> {
+        match self.format {
+            Format::Csv => {
+                let s = &summary.stats;
+                writeln!(
+                    self.out,
+                    "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                    summary.generation,
+                    summary.age,
+                    summary.survivors,
+                    summary.mean_energy,
+                    summary.max_energy,
+                    s.food_eaten,
+                    s.obstructs_spawned,
+                    s.speechlets_emitted,
+                    s.being_collisions,
+                    s.oob_penalties,
+                    s.deaths,
+                    summary.max_food,
+                    s.vocab_used(),
+                    s.vocab_entropy()
+                )?;
+            }
+            Format::Jsonl => {
+                serde_json::to_writer(&mut self.out, summary)?;
+                writeln!(self.out)?;
+            }
+        }
+        self.out.flush()
+    }
+}
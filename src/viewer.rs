@@ -0,0 +1,137 @@
+use std::{env, path::PathBuf};
+
+use ggez::{
+    conf::{NumSamples, WindowMode, WindowSetup},
+    event,
+    glam::*,
+    graphics::{Canvas, Color, DrawParam, Image, InstanceArray},
+    Context, GameResult,
+};
+
+use crate::{brain::Brain, World};
+
+struct MainState<const D: usize, M: Brain> {
+    being_instances: InstanceArray,
+    obstruct_instances: InstanceArray,
+    food_instances: InstanceArray,
+    speechlet_instances: InstanceArray,
+    world: World<D, M>,
+    vis_frequency: usize,
+}
+
+impl<const D: usize, M: Brain> MainState<D, M> {
+    fn new(ctx: &mut Context, w: World<D, M>, vis_frequency: usize) -> GameResult<MainState<D, M>> {
+        let being = Image::from_path(ctx, "/red_circle.png")?;
+        let obstruct = Image::from_path(ctx, "/white_circle.png")?;
+        let food = Image::from_path(ctx, "/green_circle.png")?;
+        let speechlet = Image::from_path(ctx, "/blue_circle.png")?;
+
+        let being_instances = InstanceArray::new(ctx, being);
+        let obstruct_instances = InstanceArray::new(ctx, obstruct);
+        let food_instances = InstanceArray::new(ctx, food);
+        let speechlet_instances = InstanceArray::new(ctx, speechlet);
+
+        Ok(MainState {
+            being_instances,
+            obstruct_instances,
+            food_instances,
+            speechlet_instances,
+            world: w,
+            vis_frequency,
+        })
+    }
+}
+
+impl<const D: usize, M: Brain> event::EventHandler<ggez::GameError> for MainState<D, M> {
+    fn update(&mut self, _ctx: &mut Context) -> Result<(), ggez::GameError> {
+        if let Some(summary) = self.world.step(1) {
+            println!("{}", summary);
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> Result<(), ggez::GameError> {
+        let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+        let cfg = &self.world.config;
+        if self.world.generation.is_multiple_of(self.vis_frequency) {
+            self.speechlet_instances
+                .set(self.world.speechlets.iter().map(|(_, s)| {
+                    let xy = s.pos;
+                    DrawParam::new()
+                        .scale(Vec2::new(1., 1.) / 512. * s.radius)
+                        .dest(xy)
+                        .offset(Vec2::new(256., 256.))
+                        .color(Color::new(1., 1., 1., s.age / cfg.s_start_age))
+                }));
+
+            self.food_instances
+                .set(self.world.foods.iter().map(|(_, f)| {
+                    let xy = f.pos - Vec2::new(cfg.f_radius, cfg.f_radius);
+                    DrawParam::new()
+                        .dest(xy)
+                        .scale(Vec2::new(1., 1.) / 2048. * 2. * cfg.f_radius)
+                        .color(Color::new(1., 1., 1., f.val / cfg.f_val))
+                }));
+
+            self.obstruct_instances
+                .set(self.world.obstructs.iter().map(|(_, o)| {
+                    let xy = o.pos;
+                    DrawParam::new()
+                        .dest(xy)
+                        .scale(Vec2::new(1., 1.) / 800. * 2. * cfg.o_radius)
+                        .color(Color::new(1., 1., 1., o.age / cfg.o_start_health))
+                }));
+
+            self.being_instances
+                .set(self.world.beings_and_models.iter().map(|(_, (b, _))| {
+                    let xy = b.pos;
+                    DrawParam::new()
+                        .scale(Vec2::new(1., 1.) / 400. * 2. * cfg.b_radius)
+                        .dest(xy)
+                        .offset(Vec2::new(200., 200.))
+                        .rotation(b.rotation)
+                        .color(Color::new(1., 1., 1., b.energy / cfg.b_start_energy))
+                }));
+
+            let param = DrawParam::new();
+            canvas.draw(&self.speechlet_instances, param);
+            canvas.draw(&self.food_instances, param);
+            canvas.draw(&self.obstruct_instances, param);
+            canvas.draw(&self.being_instances, param);
+        }
+        canvas.finish(ctx)
+    }
+}
+
+// opens a window the size of the world and steps it once per frame
+pub fn run<const D: usize, M: Brain + 'static>(world: World<D, M>, vis_frequency: usize) -> GameResult {
+    let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        let mut path = PathBuf::from(manifest_dir);
+        path.push("resources");
+        path
+    } else {
+        PathBuf::from("./resources")
+    };
+
+    let w = world.config.w_float();
+    let cb = ggez::ContextBuilder::new("spritebatch", "ggez")
+        .add_resource_path(resource_dir)
+        .window_mode(WindowMode {
+            width: w,
+            height: w,
+
+            ..Default::default()
+        })
+        .window_setup(WindowSetup {
+            title: String::from("neuralang"),
+            vsync: false,
+            samples: NumSamples::One,
+            srgb: false,
+            ..Default::default()
+        });
+
+    let (mut ctx, event_loop) = cb.build()?;
+
+    let state = MainState::new(&mut ctx, world, vis_frequency)?;
+    event::run(ctx, event_loop, state)
+}
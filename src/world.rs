@@ -0,0 +1,1717 @@
+use std::{f32::consts::PI, fmt, iter::zip};
+
+use glam::*;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use slotmap::{DefaultKey, SecondaryMap, SlotMap};
+
+use crate::{
+    being_nn::SumFxModel,
+    bench::{run_phase, Phase, PhaseTimings},
+    brain::{Brain, Observation},
+    config::{Acoustics, Occlusion, SimConfig, SpeechChannel},
+    consts::*,
+    events::{Event, EventKind, EventLog, EventType},
+    stats::GenerationStats,
+};
+
+// maps 2D space-partition index to 1D Vec index
+fn two_to_one((i, j): (usize, usize), n_cells: usize) -> usize {
+    i * n_cells + j
+}
+
+fn dir_from_theta(theta: f32) -> Vec2 {
+    Vec2::from_angle(theta)
+}
+
+fn same_partition_index((a, b): (usize, usize), (c, d): (usize, usize)) -> bool {
+    a == c && b == d
+}
+
+// maps an entity's position to the cell that contains its centre
+pub fn pos_to_cell(pos: Vec2, cfg: &SimConfig) -> (usize, usize) {
+    let c = cfg.cell_size_float();
+    let i = ((pos[0] - (pos[0] % c)) / c) as usize;
+    let j = ((pos[1] - (pos[1] % c)) / c) as usize;
+
+    (i, j)
+}
+
+pub fn lef_border_trespass(i: f32, r: f32) -> bool {
+    i - r <= 1.
+}
+
+pub fn rig_border_trespass(i: f32, r: f32, w: f32) -> bool {
+    i + r >= w - 1.
+}
+
+pub fn top_border_trespass(j: f32, r: f32) -> bool {
+    j - r <= 1.
+}
+
+pub fn bot_border_trespass(j: f32, r: f32, w: f32) -> bool {
+    j + r >= w - 1.
+}
+
+// out of bounds
+pub fn oob(xy: Vec2, r: f32, cfg: &SimConfig) -> bool {
+    let (x, y) = (xy[0], xy[1]);
+    let w = cfg.w_float();
+    lef_border_trespass(x, r)
+        || rig_border_trespass(x, r, w)
+        || top_border_trespass(y, r)
+        || bot_border_trespass(y, r, w)
+}
+
+pub fn b_collides_b(
+    b1: &Being,
+    b2: &Being,
+    cfg: &SimConfig,
+) -> (f32, f32, Vec2, [f32; 3 + GENOME_LEN]) {
+    let c1c2 = b2.pos - b1.pos;
+    let centre_dist = c1c2.length();
+    let (r1, r2) = (b1.radius, b2.radius);
+
+    let other_genome = b2.genome;
+    let rel_vec = [
+        b1.pos.angle_between(b2.pos) / PI,
+        centre_dist / cfg.b_fov_px(),
+        b2.energy / cfg.b_start_energy,
+    ];
+
+    let mut full_vec = [0.; 3 + GENOME_LEN];
+    full_vec[..3].copy_from_slice(&rel_vec);
+    full_vec[3..].copy_from_slice(&other_genome);
+
+    (r1 + r2 - centre_dist, centre_dist, c1c2, full_vec)
+}
+
+pub fn b_collides_o(b: &Being, o: &Obstruct, cfg: &SimConfig) -> (f32, f32, Vec2, [f32; 5]) {
+    let c1c2 = o.pos - b.pos;
+    let centre_dist = c1c2.length();
+    let (r1, r2) = (b.radius, cfg.o_radius);
+
+    (
+        r1 + r2 - centre_dist,
+        centre_dist,
+        c1c2,
+        [
+            0.,
+            centre_dist / cfg.b_fov_px(),
+            b.pos.angle_between(o.pos) / PI,
+            o.age / cfg.o_start_health,
+            1.,
+        ],
+    )
+}
+
+pub fn b_collides_f(b: &Being, f: &Food, cfg: &SimConfig) -> (f32, [f32; 5]) {
+    let centre_dist = b.pos.distance(f.pos);
+    let (r1, r2) = (b.radius, cfg.f_radius);
+    (
+        r1 + r2 - centre_dist,
+        [
+            1.,
+            centre_dist / cfg.b_fov_px(),
+            b.pos.angle_between(f.pos) / PI,
+            f.val / cfg.f_val,
+            f.age / cfg.f_start_age,
+        ],
+    )
+}
+
+// the egocentric part of an obs_version 2 row, how b sees something at pos moving at vel: its
+// distance over b_fov_px, the sine and cosine of its bearing from b's heading, then its velocity
+// relative to b's along and across that heading, in px per tick
+pub fn egocentric(b: &Being, pos: Vec2, vel: Vec2, cfg: &SimConfig) -> [f32; 5] {
+    let heading = dir_from_theta(b.rotation);
+    let to = pos - b.pos;
+    let bearing = if to == Vec2::ZERO {
+        0.
+    } else {
+        heading.angle_between(to)
+    };
+    let dv = vel - b.velocity;
+    [
+        to.length() / cfg.b_fov_px(),
+        bearing.sin(),
+        bearing.cos(),
+        dv.dot(heading),
+        dv.dot(heading.perp()),
+    ]
+}
+
+// what b1 sees of b2. version 1 is b_collides_b's vector, version 2 the egocentric part then
+// energy and genome
+pub fn being_row(b1: &Being, b2: &Being, cfg: &SimConfig) -> Vec<f32> {
+    match cfg.obs_version {
+        1 => b_collides_b(b1, b2, cfg).3.to_vec(),
+        _ => [
+            &egocentric(b1, b2.pos, b2.velocity, cfg)[..],
+            &[b2.energy / cfg.b_start_energy],
+            &b2.genome,
+        ]
+        .concat(),
+    }
+}
+
+// version 2 keeps the 1 and the distance in front, so whatever reads those still can
+pub fn food_row(b: &Being, f: &Food, cfg: &SimConfig) -> Vec<f32> {
+    match cfg.obs_version {
+        1 => b_collides_f(b, f, cfg).1.to_vec(),
+        _ => [
+            &[1.],
+            &egocentric(b, f.pos, Vec2::ZERO, cfg)[..],
+            &[f.val / cfg.f_val, f.age / cfg.f_start_age],
+        ]
+        .concat(),
+    }
+}
+
+pub fn obstruct_row(b: &Being, o: &Obstruct, cfg: &SimConfig) -> Vec<f32> {
+    match cfg.obs_version {
+        1 => b_collides_o(b, o, cfg).3.to_vec(),
+        _ => [
+            &[0.],
+            &egocentric(b, o.pos, Vec2::ZERO, cfg)[..],
+            &[o.age / cfg.o_start_health, 1.],
+        ]
+        .concat(),
+    }
+}
+
+// a heard speechlet: the sound, scaled by how much of it gets through walls, and, with
+// s_spatial_features, the sine and cosine of its bearing from the hearer's heading, how loud it
+// still is and its age. loudness falls from 1 at the source to 0 at the edge of the speechlet's
+// current radius and is scaled the same way. with s_speaker_features, whether the hearer spoke
+// it, the speaker's id as a point on the unit circle, the same point for everything one speaker
+// says, and whether the speaker is kin. kin is of the same clan, as every genome is still zeros
+pub fn speechlet_row(b: &Being, s: &Speechlet, gain: f32, cfg: &SimConfig) -> Vec<f32> {
+    let mut row: Vec<f32> = s.speechlet.iter().map(|v| v * gain).collect();
+    if cfg.s_spatial_features {
+        let [_, sin, cos, _, _] = egocentric(b, s.pos, Vec2::ZERO, cfg);
+        let loudness = (1. - b.pos.distance(s.pos) / s.radius).max(0.) * gain;
+        row.extend([sin, cos, loudness, s.age / cfg.s_start_age]);
+    }
+    if cfg.s_speaker_features {
+        let (sin, cos) = speaker_angle(s.speaker).sin_cos();
+        row.extend([
+            f32::from(s.speaker == b.id),
+            sin,
+            cos,
+            f32::from(s.speaker_clan == b.clan),
+        ]);
+    }
+    row
+}
+
+// what a being with the given outputs says, and the token it picked on a discrete channel.
+// the continuous channel has always spoken from the spawn gate on, one value short of the speech
+// head, and is left that way so old runs repeat. tokens are picked from the speech head itself,
+// ties going to the lower token
+pub fn utterance(
+    output: &[f32; B_OUTPUT_LEN],
+    cfg: &SimConfig,
+    rng: &mut impl Rng,
+) -> ([f32; SPEECHLET_LEN], Option<usize>) {
+    let mut speechlet = [0.; SPEECHLET_LEN];
+    let vocab = &output[4..4 + cfg.s_vocab_size];
+    let scores: Vec<f32> = match cfg.s_channel {
+        SpeechChannel::Continuous => {
+            speechlet.copy_from_slice(&output[3..3 + SPEECHLET_LEN]);
+            return (speechlet, None);
+        }
+        SpeechChannel::Argmax => vocab.to_vec(),
+        SpeechChannel::Gumbel => vocab
+            .iter()
+            .map(|v| {
+                let u: f32 = rng.gen_range(f32::EPSILON..1.);
+                v / cfg.s_gumbel_temperature - (-u.ln()).ln()
+            })
+            .collect(),
+    };
+    let token =
+        (1..scores.len()).fold(0, |best, t| if scores[t] > scores[best] { t } else { best });
+    speechlet[token] = 1.;
+    (speechlet, Some(token))
+}
+
+// ids spread by the golden angle, so consecutive ids land far apart
+fn speaker_angle(id: usize) -> f32 {
+    use std::f64::consts::{PI, TAU};
+    (id as f64 * PI * (3. - 5f64.sqrt())).rem_euclid(TAU) as f32
+}
+
+pub fn b_collides_s(b: &Being, s: &Speechlet, cfg: &SimConfig) -> f32 {
+    let c1c2 = s.pos - b.pos;
+    let centre_dist = c1c2.length();
+    let (r1, r2) = (b.radius, cfg.s_radius);
+
+    r1 + r2 - centre_dist
+}
+
+// whether being b sees something at pos: close enough, inside its cone and outside its blind
+// spot. the disc of cells it senses is cut down to this, hearing is not
+pub fn in_view(b: &Being, pos: Vec2, cfg: &SimConfig) -> bool {
+    let to = pos - b.pos;
+    let range = cfg.b_view_range_px();
+    if range > 0. && to.length() > range {
+        return false;
+    }
+    if to == Vec2::ZERO {
+        return true;
+    }
+    let off = dir_from_theta(b.rotation)
+        .angle_between(to)
+        .abs()
+        .to_degrees();
+    off <= cfg.b_view_half_angle_degrees && 180. - off >= cfg.b_blind_half_angle_degrees
+}
+
+// how many of the walls the straight line from a being at from to something at to passes
+// through. only walls nearer than the thing count, so an obstruct never hides itself
+pub fn walls_between(from: Vec2, to: Vec2, walls: &[Vec2], cfg: &SimConfig) -> usize {
+    let line = to - from;
+    let (len, len_sq) = (line.length(), line.length_squared());
+    walls
+        .iter()
+        .filter(|wall| {
+            let to_wall = **wall - from;
+            if to_wall.length() >= len {
+                return false;
+            }
+            let t = (to_wall.dot(line) / len_sq).clamp(0., 1.);
+            (from + line * t).distance(**wall) < cfg.o_radius
+        })
+        .count()
+}
+
+// whether any of the walls is in the way
+pub fn is_occluded(from: Vec2, to: Vec2, walls: &[Vec2], cfg: &SimConfig) -> bool {
+    walls_between(from, to, walls, cfg) > 0
+}
+
+// how much of a sound made at from is left by the time it reaches to, 0 if it does not get there
+pub fn sound_gain(from: Vec2, to: Vec2, walls: &[Vec2], cfg: &SimConfig) -> f32 {
+    match cfg.s_acoustics {
+        Acoustics::Off => 1.,
+        Acoustics::Damp => cfg
+            .s_wall_damping
+            .powi(walls_between(from, to, walls, cfg) as i32),
+        Acoustics::Block if is_occluded(from, to, walls, cfg) => 0.,
+        Acoustics::Block => 1.,
+    }
+}
+
+// how far along a ray from from in direction dir it first meets a circle, 0 if it starts inside
+pub fn ray_hit(from: Vec2, dir: Vec2, centre: Vec2, r: f32) -> Option<f32> {
+    let to = centre - from;
+    let along = to.dot(dir);
+    let off_sq = to.length_squared() - along * along;
+    if off_sq > r * r {
+        return None;
+    }
+    let half_chord = (r * r - off_sq).sqrt();
+    if along + half_chord < 0. {
+        return None;
+    }
+    Some((along - half_chord).max(0.))
+}
+
+pub fn is_border_in_sight(pos: Vec2, rot: f32, cfg: &SimConfig) -> [f32; 4] {
+    let (x, y) = (pos.x, pos.y);
+    let mut rel_vec: [f32; 4] = [1., 0., 1., 0.];
+    let w = cfg.w_float();
+    let fov_px = cfg.b_fov_px();
+    if x + fov_px > w {
+        rel_vec[0] = (w - x) / fov_px;
+        rel_vec[1] = rot + 0.5;
+    } else if x - fov_px < 0. {
+        rel_vec[0] = x / fov_px;
+        rel_vec[1] = rot - 0.5;
+    }
+    if y + fov_px > w {
+        rel_vec[2] = (w - y) / fov_px;
+        rel_vec[3] = rot + 1.;
+    } else if y - fov_px < 0. {
+        rel_vec[2] = y / fov_px;
+        rel_vec[3] = rot;
+    }
+
+    rel_vec
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Being {
+    pub(crate) pos: Vec2,
+    pub(crate) radius: f32,
+    pub(crate) rotation: f32,
+    pub(crate) energy: f32,
+    pub(crate) genome: [f32; GENOME_LEN],
+    #[serde(default)]
+    pub(crate) clan: usize, // the line it descends from, see World::breed
+
+    pub(crate) cell: (usize, usize),
+    pub(crate) id: usize,
+
+    pub(crate) pos_update: Vec2,
+    #[serde(default)]
+    pub(crate) velocity: Vec2, // how far it moved last tick
+    pub(crate) energy_update: f32,
+    pub(crate) rotation_update: f32,
+
+    pub(crate) being_inputs: Vec<Vec<f32>>,
+    pub(crate) food_obstruct_inputs: Vec<Vec<f32>>,
+    pub(crate) speechlet_inputs: Vec<Vec<f32>>,
+    #[serde(default)]
+    pub(crate) retina_inputs: Vec<f32>, // from the last substep only, it has a fixed size
+
+    pub(crate) output: [f32; B_OUTPUT_LEN],
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Obstruct {
+    pub(crate) pos: Vec2,
+    pub(crate) age: f32,
+    #[allow(dead_code)]
+    pub(crate) id: usize,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Food {
+    pub(crate) pos: Vec2,
+    pub(crate) val: f32,
+    pub(crate) eaten: bool,
+    pub(crate) age: f32,
+
+    pub(crate) is_flesh: bool,
+    pub(crate) id: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Speechlet {
+    pub(crate) speechlet: [f32; SPEECHLET_LEN],
+    pub(crate) pos: Vec2,
+    pub(crate) radius: f32,
+    pub(crate) age: f32,
+    #[serde(default)]
+    pub(crate) speaker: usize,
+    #[serde(default)]
+    pub(crate) speaker_clan: usize,
+    #[serde(default)]
+    pub(crate) speaker_genome: [f32; GENOME_LEN],
+
+    pub(crate) recepient_being_ids: Vec<usize>,
+}
+
+// read-only views for code outside the crate, the simulation itself works on the fields
+
+impl Being {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn clan(&self) -> usize {
+        self.clan
+    }
+
+    pub fn pos(&self) -> Vec2 {
+        self.pos
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn energy(&self) -> f32 {
+        self.energy
+    }
+
+    pub fn genome(&self) -> &[f32; GENOME_LEN] {
+        &self.genome
+    }
+
+    pub fn output(&self) -> &[f32; B_OUTPUT_LEN] {
+        &self.output
+    }
+}
+
+impl Obstruct {
+    pub fn pos(&self) -> Vec2 {
+        self.pos
+    }
+
+    pub fn age(&self) -> f32 {
+        self.age
+    }
+}
+
+impl Food {
+    pub fn pos(&self) -> Vec2 {
+        self.pos
+    }
+
+    pub fn val(&self) -> f32 {
+        self.val
+    }
+
+    pub fn is_flesh(&self) -> bool {
+        self.is_flesh
+    }
+}
+
+impl Speechlet {
+    pub fn speechlet(&self) -> &[f32; SPEECHLET_LEN] {
+        &self.speechlet
+    }
+
+    pub fn pos(&self) -> Vec2 {
+        self.pos
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn age(&self) -> f32 {
+        self.age
+    }
+
+    // the id of the being that spoke it
+    pub fn speaker(&self) -> usize {
+        self.speaker
+    }
+
+    pub fn speaker_clan(&self) -> usize {
+        self.speaker_clan
+    }
+
+    pub fn speaker_genome(&self) -> &[f32; GENOME_LEN] {
+        &self.speaker_genome
+    }
+}
+
+// M is what drives the beings, the evolved SumFxModel unless a world is built otherwise
+pub struct World<const D: usize, M: Brain = SumFxModel<BACKEND>> {
+    pub(crate) config: SimConfig,
+    pub(crate) seed: u64,
+    pub(crate) rng: WorldRng, // the only source of randomness in a world, see World::new
+
+    pub(crate) beings_and_models: SlotMap<DefaultKey, (Being, M)>,
+    pub(crate) obstructs: SlotMap<DefaultKey, Obstruct>,
+    pub(crate) foods: SlotMap<DefaultKey, Food>,
+    pub(crate) speechlets: SlotMap<DefaultKey, Speechlet>,
+
+    pub(crate) being_cells: Vec<Vec<DefaultKey>>,
+    pub(crate) obstruct_cells: Vec<Vec<DefaultKey>>,
+    pub(crate) food_cells: Vec<Vec<DefaultKey>>,
+    pub(crate) speechlet_cells: Vec<Vec<DefaultKey>>,
+
+    pub(crate) being_id: usize,
+    pub(crate) ob_id: usize,
+    pub(crate) food_id: usize,
+
+    pub(crate) being_deaths: Vec<(DefaultKey, Vec2)>,
+    pub(crate) obstruct_deaths: Vec<(DefaultKey, Vec2)>,
+    pub(crate) food_deaths: Vec<(DefaultKey, Vec2)>,
+    pub(crate) speechlet_deaths: Vec<(DefaultKey, Vec2)>,
+
+    pub(crate) fov_indices: Vec<(isize, isize)>, // cells a being senses, a disc of b_fov
+    pub(crate) contact_indices: Vec<(isize, isize)>, // cells it can touch, a square of contact_reach
+
+    pub(crate) age: usize,
+    pub(crate) generation: usize,
+    pub(crate) max_food: usize,
+    pub(crate) last_survivors: Vec<M>,
+    pub(crate) last_survivor_energies: Vec<f32>, // at reworld, same order as last_survivors
+    pub(crate) last_survivor_clans: Vec<usize>,  // same order as last_survivors
+    pub(crate) stats: GenerationStats,
+    pub(crate) events: EventLog,
+    pub(crate) batch_inference: bool, // Brain::act_batch rather than act per being, on by default
+    pub(crate) parallel_collisions: bool, // check_collisions reads on rayon's threads, on by default
+}
+
+// what reworld reports about the generation it just closed
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationSummary {
+    pub generation: usize,
+    pub age: usize,
+    pub survivors: usize, // 0 means extinction, the next generation is bred from last_survivors
+    pub mean_energy: f32, // over the survivors
+    pub max_energy: f32,
+    #[serde(flatten)]
+    pub stats: GenerationStats,
+    pub max_food: usize,
+}
+
+impl fmt::Display for GenerationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "generation: {}, world age: {}, survivors: {}, max food: {}",
+            self.generation, self.age, self.survivors, self.max_food
+        )?;
+        if self.survivors == 0 {
+            write!(f, " (extinction)")?;
+        }
+        Ok(())
+    }
+}
+
+// what check_collisions' read phase found for one being. its inputs and position update are
+// its own business, the rest waits for the write phase
+struct Contacts {
+    key: DefaultKey,
+    being_inputs: Vec<Vec<f32>>,
+    food_obstruct_inputs: Vec<Vec<f32>>,
+    speechlet_inputs: Vec<Vec<f32>>,
+    retina: Vec<f32>,
+    pos_update: Vec2,
+    effects: Vec<Contact>, // in the order they were met, which is the order energy changes in
+}
+
+enum Contact {
+    Bump {
+        other: usize,
+        rear: bool,
+        damage: f32,
+    },
+    Obstruct {
+        damage: f32,
+    },
+    Food(DefaultKey), // eaten unless a being earlier in the walk got to it first
+    Speechlet(DefaultKey),
+}
+
+// the parts of a world check_collisions' read phase looks at, everything but the brains
+struct CollisionView<'a> {
+    config: &'a SimConfig,
+    fov_indices: &'a [(isize, isize)],
+    contact_indices: &'a [(isize, isize)],
+    beings: SecondaryMap<DefaultKey, &'a Being>,
+    foods: &'a SlotMap<DefaultKey, Food>,
+    obstructs: &'a SlotMap<DefaultKey, Obstruct>,
+    speechlets: &'a SlotMap<DefaultKey, Speechlet>,
+    being_cells: &'a [Vec<DefaultKey>],
+    food_cells: &'a [Vec<DefaultKey>],
+    obstruct_cells: &'a [Vec<DefaultKey>],
+    speechlet_cells: &'a [Vec<DefaultKey>],
+}
+
+impl CollisionView<'_> {
+    // everything being k, in cell (i, j), senses within its field of view, then everything it
+    // touches within the few cells anything can overlap it from
+    fn contacts(&self, k: DefaultKey, ij: (usize, usize), substeps: usize) -> Contacts {
+        let b1 = self.beings[k];
+        let mut c = Contacts {
+            key: k,
+            being_inputs: vec![],
+            food_obstruct_inputs: vec![],
+            speechlet_inputs: vec![],
+            retina: vec![],
+            pos_update: b1.pos_update,
+            effects: vec![],
+        };
+        self.sense(k, ij, &mut c);
+        self.touch(k, ij, substeps as f32, &mut c);
+        c
+    }
+
+    // the cells at the given offsets from (i, j) that lie inside the world
+    fn cells<'b>(
+        &self,
+        (i, j): (usize, usize),
+        offsets: &'b [(isize, isize)],
+    ) -> impl Iterator<Item = usize> + 'b {
+        let n_cells = self.config.n_cells;
+        let w = n_cells as isize;
+        offsets.iter().filter_map(move |(di, dj)| {
+            let (ni, nj) = ((i as isize) + di, (j as isize) + dj);
+            if ni < 0 || ni >= w || nj < 0 || nj >= w {
+                return None;
+            }
+            Some(two_to_one((ni as usize, nj as usize), n_cells))
+        })
+    }
+
+    fn sense(&self, k: DefaultKey, ij: (usize, usize), c: &mut Contacts) {
+        let cfg = &self.config;
+        let b1 = self.beings[k];
+        let all_around = cfg.b_sees_all_around();
+        let walls: Vec<Vec2> =
+            if cfg.b_occlusion == Occlusion::Off && cfg.s_acoustics == Acoustics::Off {
+                vec![]
+            } else {
+                self.cells(ij, self.fov_indices)
+                    .flat_map(|nij| &self.obstruct_cells[nij])
+                    .map(|ob_id| self.obstructs[*ob_id].pos)
+                    .collect()
+            };
+        // the row for something at pos, if it is in sight at all
+        let row = |pos: Vec2, values: &[f32]| -> Option<Vec<f32>> {
+            if !all_around && !in_view(b1, pos, cfg) {
+                return None;
+            }
+            let mut row = Vec::from(values);
+            match cfg.b_occlusion {
+                Occlusion::Off => {}
+                Occlusion::Exclude if is_occluded(b1.pos, pos, &walls, cfg) => return None,
+                Occlusion::Exclude => {}
+                Occlusion::Flag => {
+                    row.push(f32::from(u8::from(is_occluded(b1.pos, pos, &walls, cfg))))
+                }
+            }
+            Some(row)
+        };
+
+        for nij in self.cells(ij, self.fov_indices) {
+            for id2 in &self.being_cells[nij] {
+                if *id2 == k {
+                    continue;
+                }
+                let other = self.beings[*id2];
+                c.being_inputs
+                    .extend(row(other.pos, &being_row(b1, other, cfg)));
+            }
+
+            for f_id in &self.food_cells[nij] {
+                let f = &self.foods[*f_id];
+                c.food_obstruct_inputs
+                    .extend(row(f.pos, &food_row(b1, f, cfg)));
+            }
+
+            for ob_id in &self.obstruct_cells[nij] {
+                let o = &self.obstructs[*ob_id];
+                c.food_obstruct_inputs
+                    .extend(row(o.pos, &obstruct_row(b1, o, cfg)));
+            }
+
+            for s_id in &self.speechlet_cells[nij] {
+                // each speechlet is heard once, a being meets it at most once per pass
+                let sp = &self.speechlets[*s_id];
+                if !cfg.s_self_hearing && sp.speaker == b1.id {
+                    continue;
+                }
+                let overlap = b_collides_s(b1, sp, cfg);
+
+                if overlap > 0. && !sp.recepient_being_ids.contains(&b1.id) {
+                    // a blocked speechlet is not used up, the being may still hear it elsewhere
+                    let gain = sound_gain(sp.pos, b1.pos, &walls, cfg);
+                    if gain > 0. {
+                        c.speechlet_inputs.push(speechlet_row(b1, sp, gain, cfg));
+                        c.effects.push(Contact::Speechlet(*s_id));
+                    }
+                }
+            }
+        }
+
+        if cfg.b_retina_rays > 0 {
+            c.retina = self.retina(k, ij);
+        }
+    }
+
+    // what each of b_retina_rays rays, spread evenly across the view, hits first: its distance
+    // over the sight range, 1 if nothing is in reach, then the brightness the viewer draws the
+    // being, food or obstruct with in that one's channel
+    fn retina(&self, k: DefaultKey, ij: (usize, usize)) -> Vec<f32> {
+        let cfg = &self.config;
+        let b1 = self.beings[k];
+
+        // (centre, radius, channel, brightness)
+        let mut targets: Vec<(Vec2, f32, usize, f32)> = vec![];
+        for nij in self.cells(ij, self.fov_indices) {
+            for id2 in &self.being_cells[nij] {
+                let b2 = self.beings[*id2];
+                if *id2 != k {
+                    targets.push((b2.pos, b2.radius, 1, b2.energy / cfg.b_start_energy));
+                }
+            }
+            for f_id in &self.food_cells[nij] {
+                let f = &self.foods[*f_id];
+                targets.push((f.pos, cfg.f_radius, 2, f.val / cfg.f_val));
+            }
+            for ob_id in &self.obstruct_cells[nij] {
+                let o = &self.obstructs[*ob_id];
+                targets.push((o.pos, cfg.o_radius, 3, o.age / cfg.o_start_health));
+            }
+        }
+
+        let range = cfg.b_sight_px();
+        let span = cfg
+            .b_view_half_angle_degrees
+            .min(180. - cfg.b_blind_half_angle_degrees);
+        let n = cfg.b_retina_rays;
+        let mut retina = vec![0.; n * RETINA_CHANNELS];
+        for (r, ray) in retina.chunks_mut(RETINA_CHANNELS).enumerate() {
+            let off = -span + 2. * span * (r as f32 + 0.5) / n as f32;
+            let dir = dir_from_theta(b1.rotation + off.to_radians());
+
+            ray[0] = 1.;
+            let nearest = targets
+                .iter()
+                .filter_map(|(pos, radius, ch, lum)| {
+                    ray_hit(b1.pos, dir, *pos, *radius).map(|t| (t, *ch, *lum))
+                })
+                .filter(|(t, _, _)| *t <= range)
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            if let Some((t, ch, lum)) = nearest {
+                ray[0] = t / range;
+                ray[ch] = lum;
+            }
+        }
+        retina
+    }
+
+    fn touch(&self, k: DefaultKey, ij: (usize, usize), s: f32, c: &mut Contacts) {
+        let cfg = &self.config;
+        let b1 = self.beings[k];
+
+        for nij in self.cells(ij, self.contact_indices) {
+            for id2 in &self.being_cells[nij] {
+                if *id2 == k {
+                    continue;
+                }
+                let other = self.beings[*id2];
+                let (overlap, centre_dist, c1c2, _) = b_collides_b(b1, other, cfg);
+
+                if overlap > 0. {
+                    let d_p = overlap / centre_dist * c1c2;
+                    let half_dist = d_p / 1.5;
+
+                    let new_pos = b1.pos - half_dist;
+                    if !oob(new_pos, b1.radius, cfg) {
+                        c.pos_update -= half_dist;
+                    }
+
+                    let b1_dir = dir_from_theta(b1.rotation);
+                    let axis_alignment = b1_dir.dot(c1c2.normalize());
+
+                    let damage = if axis_alignment > 0. {
+                        cfg.b_headon_damage * axis_alignment / s
+                    } else {
+                        cfg.b_rear_damage * axis_alignment.abs() / s
+                    };
+                    c.effects.push(Contact::Bump {
+                        other: other.id,
+                        rear: axis_alignment <= 0.,
+                        damage,
+                    });
+                }
+            }
+
+            for f_id in &self.food_cells[nij] {
+                let f = &self.foods[*f_id];
+                let (overlap, _) = b_collides_f(b1, f, cfg);
+
+                if overlap > 0. && !f.eaten {
+                    c.effects.push(Contact::Food(*f_id));
+                }
+            }
+
+            for ob_id in &self.obstruct_cells[nij] {
+                let o = &self.obstructs[*ob_id];
+                let (overlap, centre_dist, c1c2, _) = b_collides_o(b1, o, cfg);
+
+                if overlap > 0. {
+                    let d_p = overlap / centre_dist * c1c2;
+                    let half_dist = d_p / 1.5;
+                    c.pos_update -= half_dist;
+
+                    let b_dir = dir_from_theta(b1.rotation);
+                    let axis_alignment = b_dir.dot(c1c2.normalize());
+
+                    if axis_alignment > 0. {
+                        c.effects.push(Contact::Obstruct {
+                            damage: cfg.headon_b_hits_o_damage * axis_alignment / s,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn empty_cells(n_cells: usize) -> Vec<Vec<DefaultKey>> {
+    (0..(n_cells + 1).pow(2)).map(|_| Vec::new()).collect()
+}
+
+impl<const D: usize, M: Brain> World<D, M> {
+    // everything random in a world, from placement to weight init and mutation noise, is drawn
+    // from rng, so the same config and seed always produce the same run
+    pub fn new(config: SimConfig, seed: u64) -> Self {
+        // configs built in code never went through from_file, catch them here rather than
+        // deep inside a step
+        if let Err(e) = config.validate() {
+            panic!("{}", e);
+        }
+        let fov = config.b_fov;
+        let reach = config.contact_reach();
+
+        World {
+            seed,
+            rng: WorldRng::seed_from_u64(seed),
+
+            beings_and_models: SlotMap::new(),
+            obstructs: SlotMap::new(),
+            foods: SlotMap::new(),
+            speechlets: SlotMap::new(),
+
+            being_cells: empty_cells(config.n_cells),
+            obstruct_cells: empty_cells(config.n_cells),
+            food_cells: empty_cells(config.n_cells),
+            speechlet_cells: empty_cells(config.n_cells),
+
+            being_id: 0,
+            ob_id: 0,
+            food_id: 0,
+
+            being_deaths: vec![],
+            food_deaths: vec![],
+            obstruct_deaths: vec![],
+            speechlet_deaths: vec![],
+
+            fov_indices: (-fov..=fov)
+                .flat_map(|i| (-fov..=fov).map(move |j| (i, j)))
+                .filter(|(i, j)| i.pow(2) + j.pow(2) <= fov.pow(2))
+                .collect(),
+            contact_indices: (-reach..=reach)
+                .flat_map(|i| (-reach..=reach).map(move |j| (i, j)))
+                .collect(),
+
+            age: 0,
+            generation: 0,
+            max_food: config.max_food,
+            last_survivors: vec![],
+            last_survivor_energies: vec![],
+            last_survivor_clans: vec![],
+            stats: GenerationStats::default(),
+            events: EventLog::default(),
+            batch_inference: true,
+            parallel_collisions: true,
+
+            config,
+        }
+    }
+
+    // a world populated as intended, this fn mainly to relieve World::new() of some clutter
+    pub fn standard_world(config: SimConfig, seed: u64) -> Self {
+        World::seeded_world(config, seed, &[])
+    }
+
+    // a standard world whose starting beings carry the given brains instead of random ones.
+    // each brain is placed once as is, the rest of the population is filled with mutants of
+    // them in turn. no brains at all is exactly standard_world
+    pub fn seeded_world(config: SimConfig, seed: u64, brains: &[M]) -> Self {
+        let mut world = World::new(config, seed);
+        let (b_radius, w) = (world.config.b_radius, world.config.w_float());
+
+        for n in 0..world.config.b_start_count {
+            let rng = &mut world.rng;
+            let pos = Vec2::new(
+                rng.gen_range(b_radius..w - b_radius),
+                rng.gen_range(b_radius..w - b_radius),
+            );
+            let rotation = rng.gen_range(-PI..PI);
+            let model = match brains.get(n % brains.len().max(1)) {
+                None => M::random(rng, world.config.input_widths()),
+                Some(m) if n < brains.len() => m.clone(),
+                Some(m) => m.clone().mutate(0.01, rng),
+            };
+            // every random being founds a clan, mutants of a given brain share its clan
+            let clan = if brains.is_empty() {
+                n
+            } else {
+                n % brains.len()
+            };
+
+            world.add_being(
+                b_radius,
+                pos,
+                rotation,
+                world.config.b_start_energy,
+                [0.; GENOME_LEN],
+                clan,
+                model,
+            );
+        }
+
+        for _ in 0..world.max_food {
+            let pos = Vec2::new(
+                world.rng.gen_range(1.0..w - 1.),
+                world.rng.gen_range(1.0..w - 1.),
+            );
+            world.add_food(pos, world.config.f_val, false);
+        }
+
+        world
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_being(
+        &mut self,
+        radius: f32,
+        pos: Vec2,
+        rotation: f32,
+        health: f32,
+        genome: [f32; GENOME_LEN],
+        clan: usize,
+
+        model: M,
+    ) {
+        let (i, j) = pos_to_cell(pos, &self.config);
+
+        let being = Being {
+            radius,
+            pos,
+            rotation,
+            energy: health,
+            genome,
+            clan,
+
+            cell: (i, j),
+            id: self.being_id,
+
+            pos_update: Vec2::new(0., 0.),
+            velocity: Vec2::ZERO,
+            energy_update: 0.,
+            rotation_update: 0.,
+
+            being_inputs: vec![],
+            food_obstruct_inputs: vec![],
+            speechlet_inputs: vec![],
+            retina_inputs: vec![],
+
+            output: [0.; B_OUTPUT_LEN],
+        };
+
+        let k = self.beings_and_models.insert((being, model));
+        let ij = two_to_one((i, j), self.config.n_cells);
+        self.being_cells[ij].push(k);
+
+        self.being_id += 1;
+    }
+
+    pub fn add_obstruct(&mut self, pos: Vec2) {
+        let (i, j) = pos_to_cell(pos, &self.config);
+
+        let obstruct = Obstruct {
+            pos,
+            age: self.config.o_start_health,
+            id: self.ob_id,
+        };
+
+        let k = self.obstructs.insert(obstruct);
+
+        let ij = two_to_one((i, j), self.config.n_cells);
+        self.obstruct_cells[ij].push(k);
+        self.ob_id += 1;
+    }
+
+    pub fn add_food(&mut self, pos: Vec2, val: f32, is_flesh: bool) {
+        let (i, j) = pos_to_cell(pos, &self.config);
+        let f_start_age = self.config.f_start_age;
+
+        let food = Food {
+            pos,
+            val,
+            eaten: false,
+            age: {
+                if !is_flesh {
+                    f_start_age
+                } else {
+                    f_start_age / 3.
+                }
+            },
+            is_flesh,
+
+            id: self.food_id,
+        };
+
+        let k = self.foods.insert(food);
+
+        let ij = two_to_one((i, j), self.config.n_cells);
+        self.food_cells[ij].push(k);
+        self.food_id += 1;
+    }
+
+    pub fn add_speechlet(
+        &mut self,
+        speechlet: [f32; SPEECHLET_LEN],
+        pos: Vec2,
+        speaker: usize,
+        speaker_clan: usize,
+        speaker_genome: [f32; GENOME_LEN],
+    ) {
+        let (i, j) = pos_to_cell(pos, &self.config);
+
+        let speechlet = Speechlet {
+            speechlet,
+            pos,
+            radius: self.config.s_radius,
+            age: self.config.s_start_age,
+            speaker,
+            speaker_clan,
+            speaker_genome,
+
+            recepient_being_ids: vec![],
+        };
+
+        let k = self.speechlets.insert(speechlet);
+        let ij = two_to_one((i, j), self.config.n_cells);
+        self.speechlet_cells[ij].push(k);
+    }
+
+    pub fn move_beings(&mut self, substeps: usize) {
+        let s = substeps as f32;
+        let cfg = &self.config;
+
+        for _ in 0..substeps {
+            self.beings_and_models
+                .iter_mut()
+                .for_each(|(_, (being, _))| {
+                    let being_rotation = dir_from_theta(being.rotation);
+                    let move_vec = being.output[0] * being_rotation;
+                    let newxy = being.pos
+                        + (move_vec
+                            * (1. - cfg.low_energy_speed_damp_rate)
+                            * (being.energy / cfg.b_start_energy)
+                            * cfg.b_speed);
+
+                    if !oob(newxy, being.radius, cfg) {
+                        let pos_update = move_vec / s;
+                        let rot_update = (being.output[1] * PI) / s;
+
+                        being.pos_update += pos_update;
+                        being.rotation_update += (being.output[1] * PI) / s;
+
+                        being.energy_update -=
+                            (pos_update.length() / cfg.b_speed) * cfg.b_move_tire_rate;
+                        being.energy_update -= (rot_update.abs() / PI) * cfg.b_rot_tire_rate;
+                    } else {
+                        let move_vec = -dir_from_theta(being.rotation) * 1.5; // hacky
+                        being.pos_update += move_vec / s;
+
+                        being.energy_update -= cfg.oob_penalty;
+                        self.stats.oob_penalties += 1;
+                        self.events.record(
+                            being.id,
+                            being.pos,
+                            EventKind::Oob {
+                                penalty: cfg.oob_penalty,
+                            },
+                        );
+                    }
+                });
+        }
+    }
+
+    pub fn grow_speechlets(&mut self) {
+        let s_radius = self.config.s_radius;
+        self.speechlets.iter_mut().for_each(|(_, s)| {
+            s.radius += s_radius;
+        });
+    }
+
+    // a read phase works out every being's contacts in parallel, each being only reading the
+    // world, then a write phase applies them in the order the cells are walked. whatever depends
+    // on who came first, like which of two beings eats a food, is settled in the write phase, so
+    // the outcome is the same however the read phase is scheduled
+    pub fn check_collisions(&mut self, substeps: usize) {
+        let n_cells = self.config.n_cells;
+        let order: Vec<(DefaultKey, (usize, usize))> = (0..n_cells)
+            .flat_map(|i| (0..n_cells).map(move |j| (i, j)))
+            .flat_map(|ij| {
+                self.being_cells[two_to_one(ij, n_cells)]
+                    .iter()
+                    .map(move |k| (*k, ij))
+            })
+            .collect();
+
+        let view = CollisionView {
+            config: &self.config,
+            fov_indices: &self.fov_indices,
+            contact_indices: &self.contact_indices,
+            beings: self
+                .beings_and_models
+                .iter()
+                .map(|(k, (b, _))| (k, b))
+                .collect(),
+            foods: &self.foods,
+            obstructs: &self.obstructs,
+            speechlets: &self.speechlets,
+            being_cells: &self.being_cells,
+            food_cells: &self.food_cells,
+            obstruct_cells: &self.obstruct_cells,
+            speechlet_cells: &self.speechlet_cells,
+        };
+        let sense = |(k, ij): &(DefaultKey, (usize, usize))| view.contacts(*k, *ij, substeps);
+        let contacts: Vec<Contacts> = if self.parallel_collisions {
+            order.par_iter().map(sense).collect()
+        } else {
+            order.iter().map(sense).collect()
+        };
+
+        for c in contacts {
+            let (b, _) = self.beings_and_models.get_mut(c.key).unwrap();
+            b.being_inputs.extend(c.being_inputs);
+            b.food_obstruct_inputs.extend(c.food_obstruct_inputs);
+            b.speechlet_inputs.extend(c.speechlet_inputs);
+            if !c.retina.is_empty() {
+                b.retina_inputs = c.retina;
+            }
+            b.pos_update = c.pos_update;
+
+            for contact in c.effects {
+                match contact {
+                    Contact::Bump {
+                        other,
+                        rear,
+                        damage,
+                    } => {
+                        self.stats.being_collisions += 1;
+                        b.energy_update -= damage;
+                        self.events.record(
+                            b.id,
+                            b.pos,
+                            EventKind::Bump {
+                                other,
+                                rear,
+                                damage,
+                            },
+                        );
+                    }
+                    Contact::Obstruct { damage } => {
+                        b.energy_update -= damage;
+                    }
+                    Contact::Food(f_id) => {
+                        let f = self.foods.get_mut(f_id).unwrap();
+                        if !f.eaten {
+                            b.energy_update += f.val;
+                            self.food_deaths.push((f_id, f.pos));
+                            self.stats.food_eaten += 1;
+                            self.events.record(
+                                b.id,
+                                b.pos,
+                                EventKind::Eat {
+                                    food: f.id,
+                                    at: f.pos,
+                                    val: f.val,
+                                    flesh: f.is_flesh,
+                                },
+                            );
+                            f.eaten = true;
+                        }
+                    }
+                    Contact::Speechlet(s_id) => {
+                        let s = self.speechlets.get_mut(s_id).unwrap();
+                        s.recepient_being_ids.push(b.id);
+                        self.events.record(
+                            b.id,
+                            b.pos,
+                            EventKind::Hear {
+                                speechlet: s.speechlet,
+                                from: s.pos,
+                                speaker: s.speaker,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // reflect changes in rotation, translation, collision resolution, fatigue, aging, death
+    pub fn update_cells(&mut self) {
+        let cfg = &self.config;
+
+        for (k, (b, _)) in &mut self.beings_and_models {
+            let new_pos = b.pos + b.pos_update;
+            // println!("{}", b.pos_update.length());
+            b.energy += b.energy_update;
+            b.rotation += b.rotation_update;
+
+            b.energy_update = 0.;
+            b.rotation_update = 0.;
+
+            if !oob(new_pos, b.radius, cfg) {
+                b.velocity = b.pos_update;
+                b.pos = new_pos;
+                b.pos_update = Vec2::ZERO;
+
+                let (oi, oj) = b.cell;
+                let (i, j) = pos_to_cell(new_pos, cfg);
+
+                if !same_partition_index((oi, oj), (i, j)) {
+                    b.cell = (i, j);
+
+                    let oij = two_to_one((oi, oj), cfg.n_cells);
+                    let ij = two_to_one((i, j), cfg.n_cells);
+
+                    self.being_cells[oij].retain(|x| *x != k);
+                    self.being_cells[ij].push(k);
+                }
+            } else {
+                b.velocity = Vec2::ZERO;
+            }
+        }
+    }
+
+    // beings tire and/or die
+    pub fn tire_beings(&mut self) {
+        for (k, (b, _)) in &mut self.beings_and_models {
+            b.energy -= self.config.b_tire_rate;
+
+            if b.energy <= 0. {
+                self.being_deaths.push((k, b.pos));
+                self.events
+                    .record(b.id, b.pos, EventKind::Death { energy: b.energy });
+            }
+        }
+
+        self.stats.deaths += self.being_deaths.len();
+        let (scatter_radius, f_radius) = (self.config.b_scatter_radius, self.config.f_radius);
+        for (k, pos) in &self.being_deaths.clone() {
+            self.beings_and_models.remove(*k);
+            self.being_cells[two_to_one(pos_to_cell(*pos, &self.config), self.config.n_cells)]
+                .retain(|x| x != k);
+
+            for _ in 0..self.config.b_scatter_count {
+                let (theta, dist) = (
+                    self.rng.gen_range(-PI..PI),
+                    self.rng.gen_range(0.0..scatter_radius),
+                );
+                let dvec = Vec2::new(theta.cos() * dist, theta.sin() * dist);
+
+                let food_pos = *pos + dvec;
+                if !oob(food_pos, f_radius, &self.config) {
+                    self.add_food(food_pos, self.config.b_death_energy / scatter_radius, true);
+                };
+            }
+        }
+
+        self.being_deaths.clear();
+    }
+
+    // walls crack and/or crumble
+    pub fn age_obstructs(&mut self) {
+        for (k, o) in &mut self.obstructs {
+            o.age -= self.config.o_age_rate;
+
+            if o.age < 0.05 {
+                self.obstruct_deaths.push((k, o.pos));
+            }
+        }
+
+        for (k, pos) in &self.obstruct_deaths {
+            self.obstructs.remove(*k);
+            self.obstruct_cells[two_to_one(pos_to_cell(*pos, &self.config), self.config.n_cells)]
+                .retain(|x| x != k);
+        }
+
+        self.obstruct_deaths.clear();
+    }
+
+    // food grows stale and/or disappears
+    pub fn age_foods(&mut self) {
+        for (k, f) in &mut self.foods {
+            f.age -= self.config.f_rot_rate;
+            if f.age <= 0. {
+                self.food_deaths.push((k, f.pos));
+            }
+        }
+
+        for (k, pos) in &self.food_deaths {
+            self.foods.remove(*k);
+
+            self.food_cells[two_to_one(pos_to_cell(*pos, &self.config), self.config.n_cells)]
+                .retain(|x| x != k);
+        }
+
+        self.food_deaths.clear();
+    }
+
+    pub fn soften_speechlets(&mut self) {
+        for (k, s) in &mut self.speechlets {
+            s.age -= self.config.s_soften_rate;
+
+            if s.age <= 0. {
+                self.speechlet_deaths.push((k, s.pos));
+            }
+        }
+
+        for (k, pos) in &self.speechlet_deaths {
+            self.speechlets.remove(*k);
+            self.speechlet_cells[two_to_one(pos_to_cell(*pos, &self.config), self.config.n_cells)]
+                .retain(|x| x != k);
+        }
+
+        self.speechlet_deaths.clear();
+    }
+
+    pub fn repop_foods(&mut self) {
+        let w = self.config.w_float();
+
+        for _ in 0..self.config.n_food_spawn_per_step {
+            if self.foods.iter().filter(|(_, f)| !f.is_flesh).count() < self.max_food {
+                let ij = Vec2::new(self.rng.gen_range(1.0..w), self.rng.gen_range(1.0..w));
+                self.add_food(ij, self.config.f_val, false);
+            }
+        }
+    }
+
+    // has side-effects; probably not worth the effort to separate updates and effects
+    pub fn perform_being_outputs(&mut self) {
+        let mut obstruct_queue: Vec<Vec2> = Vec::new();
+        // where, what, and who said it: id, clan and genome
+        let mut speechlet_queue = Vec::new();
+        let cfg = &self.config;
+
+        let mut models: Vec<&mut M> = vec![];
+        let mut observations: Vec<Observation> = vec![];
+        for (_, (b, model)) in self.beings_and_models.iter_mut() {
+            let b: &Being = b;
+            let mut own = [0.; 5];
+            own[..4].copy_from_slice(&is_border_in_sight(b.pos, b.rotation, cfg));
+            own[4] = b.energy / cfg.b_start_energy;
+
+            models.push(model);
+            observations.push(Observation {
+                beings: &b.being_inputs,
+                food_obstructs: &b.food_obstruct_inputs,
+                speechlets: &b.speechlet_inputs,
+                own,
+                retina: &b.retina_inputs,
+            });
+        }
+        let outputs = if self.batch_inference {
+            M::act_batch(&mut models, &observations)
+        } else {
+            zip(models, &observations).map(|(m, o)| m.act(o)).collect()
+        };
+
+        zip(self.beings_and_models.values_mut(), outputs).for_each(|((b, _), output)| {
+            b.being_inputs.clear();
+            b.food_obstruct_inputs.clear();
+            b.speechlet_inputs.clear();
+
+            b.output = output;
+
+            if b.output[2] > 0. {
+                let at = b.pos + dir_from_theta(b.rotation) * 2.;
+                b.energy_update -= cfg.spawn_o_ratio * cfg.b_start_energy;
+                obstruct_queue.push(at);
+                self.events.record(b.id, b.pos, EventKind::Wall { at });
+            }
+
+            if b.output[3] > 0. {
+                let (speechlet, token) = utterance(&b.output, cfg, &mut self.rng);
+                b.energy_update -= cfg.spawn_s_ratio * cfg.b_start_energy;
+                speechlet_queue.push((b.pos, speechlet, b.id, b.clan, b.genome));
+                if let Some(t) = token {
+                    self.stats.tokens_spoken[t] += 1;
+                }
+                self.events
+                    .record(b.id, b.pos, EventKind::Speak { speechlet, token });
+            }
+        });
+
+        self.stats.obstructs_spawned += obstruct_queue.len();
+        self.stats.speechlets_emitted += speechlet_queue.len();
+        for pos in obstruct_queue {
+            self.add_obstruct(pos);
+        }
+        for (pos, speechlet, speaker, clan, genome) in speechlet_queue {
+            self.add_speechlet(speechlet, pos, speaker, clan, genome);
+        }
+    }
+
+    pub fn reworld(&mut self) -> Option<GenerationSummary> {
+        if self.beings_and_models.len() >= self.config.reworlding_threshold {
+            return None;
+        }
+
+        if self.max_food > self.config.min_food {
+            self.max_food -= self.config.max_food_reduction;
+        }
+
+        let energies: Vec<f32> = self
+            .beings_and_models
+            .values()
+            .map(|(b, _)| b.energy)
+            .collect();
+        let summary = GenerationSummary {
+            generation: self.generation,
+            age: self.age,
+            survivors: energies.len(),
+            mean_energy: energies.iter().sum::<f32>() / energies.len().max(1) as f32,
+            max_energy: energies.iter().copied().reduce(f32::max).unwrap_or(0.),
+            stats: self.stats,
+            max_food: self.max_food,
+        };
+
+        let mut survivors: Vec<(M, usize)> = self
+            .beings_and_models
+            .values()
+            .map(|(b, m)| (m.clone(), b.clan))
+            .collect();
+
+        let new_models = if survivors.is_empty() {
+            self.clanned_survivors()
+        } else {
+            self.last_survivors = survivors.iter().map(|(m, _)| m.clone()).collect();
+            self.last_survivor_clans = survivors.iter().map(|(_, c)| *c).collect();
+            self.last_survivor_energies = energies;
+            self.breed(&survivors)
+        };
+
+        self.generation += 1;
+        survivors.extend(new_models);
+        self.start_generation(survivors);
+
+        Some(summary)
+    }
+
+    // offspring of random pairs of parents, enough to top the parents up to b_start_count. a
+    // child is of the clan of the parent it takes most after, the first
+    pub(crate) fn breed(&mut self, parents: &[(M, usize)]) -> Vec<(M, usize)> {
+        let mut new_models: Vec<(M, usize)> = vec![];
+
+        while new_models.len() + parents.len() < self.config.b_start_count {
+            let (m1, clan) = parents.choose(&mut self.rng).unwrap();
+            let (m2, _) = parents.choose(&mut self.rng).unwrap();
+
+            let new_model = m1
+                .clone()
+                .crossover(m2.clone(), 0.05)
+                .mutate(0.01, &mut self.rng);
+            new_models.push((new_model, *clan));
+        }
+
+        new_models
+    }
+
+    // last_survivors with their clans. worlds saved before clans were kept give each survivor
+    // a clan of its own
+    pub(crate) fn clanned_survivors(&self) -> Vec<(M, usize)> {
+        let clan = |i: usize| self.last_survivor_clans.get(i).copied().unwrap_or(i);
+        (0..self.last_survivors.len())
+            .map(|i| (self.last_survivors[i].clone(), clan(i)))
+            .collect()
+    }
+
+    // empties the world, refills its food and scatters the given models as fresh beings of
+    // their clans
+    pub(crate) fn start_generation(&mut self, models: Vec<(M, usize)>) {
+        self.beings_and_models.clear();
+        self.foods.clear();
+        self.obstructs.clear();
+        self.speechlets.clear();
+
+        self.being_deaths.clear();
+        self.food_deaths.clear();
+        self.obstruct_deaths.clear();
+        self.speechlet_deaths.clear();
+
+        self.being_cells = empty_cells(self.config.n_cells);
+        self.obstruct_cells = empty_cells(self.config.n_cells);
+        self.food_cells = empty_cells(self.config.n_cells);
+        self.speechlet_cells = empty_cells(self.config.n_cells);
+
+        let (b_radius, w) = (self.config.b_radius, self.config.w_float());
+        for _ in 0..self.max_food {
+            let pos = Vec2::new(
+                self.rng.gen_range(1.0..w - 1.),
+                self.rng.gen_range(1.0..w - 1.),
+            );
+            self.add_food(pos, self.config.f_val, false);
+        }
+
+        self.being_id = 0;
+        self.ob_id = 0;
+        self.food_id = 0;
+
+        self.age = 0;
+        self.stats = GenerationStats::default();
+
+        for (m, clan) in models {
+            let pos = Vec2::new(
+                self.rng.gen_range(b_radius..w - b_radius),
+                self.rng.gen_range(b_radius..w - b_radius),
+            );
+            let rotation = self.rng.gen_range(-PI..PI);
+            self.add_being(
+                b_radius,
+                pos,
+                rotation,
+                self.config.b_start_energy,
+                [0.; GENOME_LEN],
+                clan,
+                m,
+            );
+        }
+    }
+
+    pub fn config(&self) -> &SimConfig {
+        &self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // ticks since the current generation began
+    pub fn age(&self) -> usize {
+        self.age
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn max_food(&self) -> usize {
+        self.max_food
+    }
+
+    // counted so far in the current generation
+    pub fn stats(&self) -> &GenerationStats {
+        &self.stats
+    }
+
+    // in slotmap order, which is the order beings act in
+    pub fn beings(&self) -> impl Iterator<Item = (&Being, &M)> {
+        self.beings_and_models.values().map(|(b, m)| (b, m))
+    }
+
+    pub fn obstructs(&self) -> impl Iterator<Item = &Obstruct> {
+        self.obstructs.values()
+    }
+
+    pub fn foods(&self) -> impl Iterator<Item = &Food> {
+        self.foods.values()
+    }
+
+    pub fn speechlets(&self) -> impl Iterator<Item = &Speechlet> {
+        self.speechlets.values()
+    }
+
+    // the parents of the current generation, what it is rebred from after an extinction
+    pub fn last_survivors(&self) -> &[M] {
+        &self.last_survivors
+    }
+
+    // copies of the n last survivors that ended their generation with the most energy
+    pub fn top_survivors(&self, n: usize) -> Vec<M> {
+        let energy = |i: usize| self.last_survivor_energies.get(i).copied().unwrap_or(0.);
+        let mut ranked: Vec<usize> = (0..self.last_survivors.len()).collect();
+        ranked.sort_by(|a, b| energy(*b).total_cmp(&energy(*a)));
+        ranked
+            .into_iter()
+            .take(n)
+            .map(|i| self.last_survivors[i].clone())
+            .collect()
+    }
+
+    // adds brains from another world to the pool the current generation was bred from and
+    // breeds that generation anew. meant for right after a reworld, while nothing has happened
+    // in the generation yet. migrants rank below the natives in top_survivors and each founds
+    // a clan new to this world
+    pub fn immigrate(&mut self, migrants: Vec<M>) {
+        if migrants.is_empty() {
+            return;
+        }
+        let natives = self.clanned_survivors();
+        let first_clan = natives.iter().map(|(_, c)| c + 1).max().unwrap_or(0);
+        self.last_survivor_clans = natives.iter().map(|(_, c)| *c).collect();
+        self.last_survivor_clans
+            .extend((first_clan..).take(migrants.len()));
+        self.last_survivor_energies
+            .resize(self.last_survivors.len(), 0.);
+        self.last_survivor_energies
+            .extend(migrants.iter().map(|_| f32::NEG_INFINITY));
+        self.last_survivors.extend(migrants);
+
+        let parents = self.clanned_survivors();
+        let mut models = parents.clone();
+        models.extend(self.breed(&parents));
+        self.start_generation(models);
+    }
+
+    // only interactions of these types are recorded, none by default
+    pub fn record_events(&mut self, types: &[EventType]) {
+        self.events.want(types);
+    }
+
+    // what was recorded since the last call
+    pub fn take_events(&mut self) -> Vec<Event> {
+        self.events.take()
+    }
+
+    // beings act in one batch per tick unless turned off, which changes the speed but never the run
+    pub fn set_batch_inference(&mut self, on: bool) {
+        self.batch_inference = on;
+    }
+
+    // like batch inference, turning this off only changes the speed
+    pub fn set_parallel_collisions(&mut self, on: bool) {
+        self.parallel_collisions = on;
+    }
+
+    // the living being with the most energy, the best guess at who is doing well mid-generation
+    pub fn champion(&self) -> Option<(&Being, &M)> {
+        self.beings_and_models
+            .values()
+            .max_by(|(b1, _), (b2, _)| b1.energy.total_cmp(&b2.energy))
+            .map(|(b, m)| (b, m))
+    }
+
+    pub fn being_by_id(&self, id: usize) -> Option<(&Being, &M)> {
+        self.beings_and_models
+            .values()
+            .find(|(b, _)| b.id == id)
+            .map(|(b, m)| (b, m))
+    }
+
+    // returns a summary whenever the step ended a generation
+    pub fn step(&mut self, substeps: usize) -> Option<GenerationSummary> {
+        self.step_profiled(substeps, None)
+    }
+
+    // step, adding the time spent in each phase to timings if given
+    pub fn step_profiled(
+        &mut self,
+        substeps: usize,
+        mut timings: Option<&mut PhaseTimings>,
+    ) -> Option<GenerationSummary> {
+        let t = &mut timings;
+
+        self.events.at(self.generation, self.age);
+        for _ in 0..substeps {
+            run_phase(t, Phase::MoveBeings, || self.move_beings(substeps));
+            run_phase(t, Phase::CheckCollisions, || {
+                self.check_collisions(substeps)
+            });
+            run_phase(t, Phase::UpdateCells, || self.update_cells());
+        }
+        run_phase(t, Phase::PerformBeingOutputs, || {
+            self.perform_being_outputs()
+        });
+        run_phase(t, Phase::GrowSpeechlets, || self.grow_speechlets());
+        run_phase(t, Phase::TireBeings, || self.tire_beings());
+        run_phase(t, Phase::Aging, || {
+            self.age_foods();
+            self.age_obstructs();
+            self.soften_speechlets();
+        });
+        run_phase(t, Phase::RepopFoods, || self.repop_foods());
+
+        let summary = run_phase(t, Phase::Reworld, || self.reworld());
+
+        self.age += 1;
+        summary
+    }
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use common::{quick_config, trajectory};
+use glam::Vec2;
+use neuralang::{
+    baselines::{GreedyForager, RandomBrain, WallBuilder},
+    brain::{Brain, InputWidths, Observation},
+    GenerationSummary, SimConfig, World, WorldRng, B_OUTPUT_LEN, GENOME_LEN,
+};
+use serde::{Deserialize, Serialize};
+
+fn first_summary<M: Brain>(w: &mut World<2, M>) -> GenerationSummary {
+    loop {
+        if let Some(summary) = w.step(1) {
+            return summary;
+        }
+    }
+}
+
+#[test]
+fn scripted_baselines_stick_to_their_script() {
+    let egocentric = SimConfig {
+        obs_version: 2,
+        ..quick_config()
+    };
+    let mut w = World::<2, GreedyForager>::standard_world(egocentric, 5);
+    let summary = first_summary(&mut w);
+    assert!(summary.stats.food_eaten > 0);
+    assert_eq!(summary.stats.obstructs_spawned, 0);
+    assert_eq!(summary.stats.speechlets_emitted, 0);
+
+    let mut w = World::<2, WallBuilder>::standard_world(quick_config(), 5);
+    let summary = first_summary(&mut w);
+    assert!(summary.stats.obstructs_spawned > 0);
+    assert_eq!(summary.stats.speechlets_emitted, 0);
+}
+
+#[test]
+fn random_baseline_follows_the_seed() {
+    let mut a = World::<2, RandomBrain>::standard_world(quick_config(), 9);
+    let mut b = World::<2, RandomBrain>::standard_world(quick_config(), 9);
+    for _ in 0..150 {
+        a.step(1);
+        b.step(1);
+        assert_eq!(trajectory(&a), trajectory(&b));
+    }
+    assert!(a.generation() > 0);
+}
+
+// walks straight ahead whatever it sees, what steering has to beat
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Straight;
+
+impl Brain for Straight {
+    type State = Straight;
+
+    fn random(_rng: &mut WorldRng, _widths: InputWidths) -> Self {
+        Straight
+    }
+
+    fn act(&mut self, _obs: &Observation) -> [f32; B_OUTPUT_LEN] {
+        let mut out = [-1.; B_OUTPUT_LEN];
+        out[0] = 1.;
+        out[1] = 0.;
+        out
+    }
+
+    fn reset(&mut self) {}
+
+    fn crossover(self, _other: Self, _weight: f32) -> Self {
+        self
+    }
+
+    fn mutate(self, _rate: f32, _rng: &mut WorldRng) -> Self {
+        self
+    }
+
+    fn to_state(&self) -> Self::State {
+        *self
+    }
+
+    fn from_state(state: &Self::State, _widths: InputWidths) -> Result<Self, String> {
+        Ok(*state)
+    }
+}
+
+// food eaten over the first generation or n ticks, whichever ends first
+fn eaten<M: Brain>(config: SimConfig, seed: u64, n: usize) -> usize {
+    let mut w = World::<2, M>::standard_world(config, seed);
+    for _ in 0..n {
+        if let Some(summary) = w.step(1) {
+            return summary.stats.food_eaten;
+        }
+    }
+    w.stats().food_eaten
+}
+
+#[test]
+fn greedy_forager_steers_on_egocentric_rows() {
+    let config = SimConfig {
+        obs_version: 2,
+        ..Default::default()
+    };
+    for seed in 0..2 {
+        let greedy = eaten::<GreedyForager>(config.clone(), seed, 1500);
+        let straight = eaten::<Straight>(config.clone(), seed, 1500);
+        assert!(
+            greedy > straight,
+            "seed {}: {} vs {}",
+            seed,
+            greedy,
+            straight
+        );
+    }
+}
+
+#[test]
+fn greedy_forager_turns_round_to_food() {
+    // obs_version 1 rows carry no bearing from the being's heading to steer by
+    assert!(GreedyForager::check_widths(SimConfig::default().input_widths()).is_err());
+
+    // a lone being facing +x and a lone food behind it, beside it or ahead of it. one being
+    // never drops below the reworlding threshold and the food is not replaced until eaten
+    let config = SimConfig {
+        obs_version: 2,
+        reworlding_threshold: 1,
+        max_food: 1,
+        min_food: 1,
+        ..Default::default()
+    };
+    assert!(GreedyForager::check_widths(config.input_widths()).is_ok());
+    let mid = Vec2::splat(config.w_float() / 2.);
+    for offset in [(-20., 0.), (-15., 10.), (0., -20.), (20., 0.)] {
+        let mut w = World::<2, GreedyForager>::new(config.clone(), 0);
+        let (r, energy) = (config.b_radius, config.b_start_energy);
+        w.add_being(r, mid, 0., energy, [0.; GENOME_LEN], 0, GreedyForager);
+        w.add_food(mid + Vec2::from(offset), config.f_val, false);
+        let reached = (0..100).any(|_| {
+            w.step(1);
+            w.stats().food_eaten > 0
+        });
+        assert!(reached, "food at {:?}", offset);
+    }
+}
@@ -0,0 +1,27 @@
+// each test crate compiles its own copy and not all of them use every helper
+#![allow(dead_code)]
+
+use neuralang::{brain::Brain, SimConfig, World};
+
+// small and short-lived so a few generations pass within a test
+pub fn quick_config() -> SimConfig {
+    SimConfig {
+        w_size: 150,
+        n_cells: 50,
+        b_start_count: 12,
+        reworlding_threshold: 6,
+        b_tire_rate: 0.1,
+        max_food: 60,
+        min_food: 10,
+        ..Default::default()
+    }
+}
+
+pub fn trajectory<M: Brain>(w: &World<2, M>) -> Vec<u32> {
+    let mut bits = vec![w.generation() as u32, w.foods().count() as u32];
+    for (b, _) in w.beings() {
+        bits.extend([b.pos().x, b.pos().y, b.rotation(), b.energy()].map(f32::to_bits));
+        bits.extend(b.output().map(f32::to_bits));
+    }
+    bits
+}
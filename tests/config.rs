@@ -0,0 +1,226 @@
+use std::{fs, path::PathBuf};
+
+use neuralang::{config::ConfigError, SimConfig, World};
+
+// a config file with the given name and contents in the temp dir, unique to this test process
+fn config_file(name: &str, text: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("samsarsa-{}-{}", std::process::id(), name));
+    fs::write(&path, text).unwrap();
+    path
+}
+
+fn load(name: &str, text: &str) -> Result<SimConfig, ConfigError> {
+    let path = config_file(name, text);
+    let config = SimConfig::from_file(&path);
+    fs::remove_file(&path).unwrap();
+    config
+}
+
+#[test]
+fn partial_files_fall_back_to_defaults() {
+    let expected = SimConfig {
+        w_size: 750,
+        b_speed: 0.75,
+        ..Default::default()
+    };
+
+    let toml = load("partial.toml", "w_size = 750\nb_speed = 0.75\n").unwrap();
+    assert_eq!(toml, expected);
+    let json = load("partial.json", r#"{"w_size": 750, "b_speed": 0.75}"#).unwrap();
+    assert_eq!(json, expected);
+
+    assert_eq!(load("empty.toml", "").unwrap(), SimConfig::default());
+    assert_eq!(load("empty.json", "{}").unwrap(), SimConfig::default());
+}
+
+#[test]
+fn bad_files_are_refused() {
+    assert!(matches!(
+        load("unknown.toml", "w_sise = 300\n"),
+        Err(ConfigError::Parse(_))
+    ));
+    assert!(matches!(
+        load("unknown.json", r#"{"w_sise": 300}"#),
+        Err(ConfigError::Parse(_))
+    ));
+    assert!(matches!(
+        load("typed.toml", "w_size = \"big\"\n"),
+        Err(ConfigError::Parse(_))
+    ));
+    assert!(matches!(
+        load("config.yaml", "w_size: 300\n"),
+        Err(ConfigError::UnsupportedFormat(ext)) if ext == "yaml"
+    ));
+    assert!(matches!(
+        SimConfig::from_file(&std::env::temp_dir().join("samsarsa-missing.toml")),
+        Err(ConfigError::Io(_))
+    ));
+
+    // parsed fine, but validated before it is handed out
+    assert!(matches!(
+        load("invalid.toml", "w_size = 301\n"),
+        Err(ConfigError::Invalid(_))
+    ));
+}
+
+#[test]
+fn every_invariant_is_checked() {
+    let cases = [
+        (
+            "w_size",
+            SimConfig {
+                n_cells: 0,
+                ..Default::default()
+            },
+        ),
+        // used to be asserted in main
+        (
+            "multiple of n_cells",
+            SimConfig {
+                w_size: 301,
+                ..Default::default()
+            },
+        ),
+        (
+            "b_radius",
+            SimConfig {
+                b_radius: 20.,
+                ..Default::default()
+            },
+        ),
+        (
+            "twice b_radius",
+            SimConfig {
+                w_size: 4,
+                n_cells: 1,
+                b_radius: 2.5,
+                ..Default::default()
+            },
+        ),
+        (
+            "twice b_radius",
+            SimConfig {
+                w_size: 2,
+                n_cells: 1,
+                b_radius: 0.5,
+                ..Default::default()
+            },
+        ),
+        (
+            "b_scatter_radius",
+            SimConfig {
+                b_scatter_radius: 0.,
+                ..Default::default()
+            },
+        ),
+        (
+            "b_fov",
+            SimConfig {
+                b_fov: -1,
+                ..Default::default()
+            },
+        ),
+        (
+            "b_fov",
+            SimConfig {
+                b_fov: 0,
+                ..Default::default()
+            },
+        ),
+        (
+            "b_view_half_angle_degrees",
+            SimConfig {
+                b_view_half_angle_degrees: 0.,
+                ..Default::default()
+            },
+        ),
+        (
+            "b_view_half_angle_degrees",
+            SimConfig {
+                b_view_half_angle_degrees: 181.,
+                ..Default::default()
+            },
+        ),
+        (
+            "b_blind_half_angle_degrees",
+            SimConfig {
+                b_blind_half_angle_degrees: 180.,
+                ..Default::default()
+            },
+        ),
+        (
+            "b_view_range",
+            SimConfig {
+                b_view_range: -1.,
+                ..Default::default()
+            },
+        ),
+        (
+            "past b_fov",
+            SimConfig {
+                b_view_range: 12.,
+                ..Default::default()
+            },
+        ),
+        (
+            "s_wall_damping",
+            SimConfig {
+                s_wall_damping: 1.5,
+                ..Default::default()
+            },
+        ),
+        (
+            "s_vocab_size",
+            SimConfig {
+                s_vocab_size: 0,
+                ..Default::default()
+            },
+        ),
+        (
+            "s_gumbel_temperature",
+            SimConfig {
+                s_gumbel_temperature: 0.,
+                ..Default::default()
+            },
+        ),
+        (
+            "obs_version",
+            SimConfig {
+                obs_version: 3,
+                ..Default::default()
+            },
+        ),
+        (
+            "min_food",
+            SimConfig {
+                min_food: 300,
+                ..Default::default()
+            },
+        ),
+        (
+            "b_start_energy",
+            SimConfig {
+                b_start_energy: 0.,
+                ..Default::default()
+            },
+        ),
+    ];
+
+    assert!(SimConfig::default().validate().is_ok());
+    for (field, config) in cases {
+        match config.validate() {
+            Err(ConfigError::Invalid(msg)) => assert!(msg.contains(field), "{}: {}", field, msg),
+            other => panic!("{}: expected invalid, got {:?}", field, other),
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "n_cells")]
+fn worlds_refuse_invalid_configs() {
+    let config = SimConfig {
+        n_cells: 0,
+        ..Default::default()
+    };
+    World::<2>::standard_world(config, 0);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{quick_config, trajectory};
+use neuralang::World;
+
+#[test]
+fn same_seed_same_trajectory() {
+    let mut w1 = World::<2>::standard_world(quick_config(), 7);
+    let mut w2 = World::<2>::standard_world(quick_config(), 7);
+
+    for _ in 0..300 {
+        w1.step(1);
+        w2.step(1);
+        assert_eq!(trajectory(&w1), trajectory(&w2));
+    }
+    assert!(
+        w1.generation() > 0,
+        "test should cover at least one reworld"
+    );
+}
+
+#[test]
+fn different_seed_different_trajectory() {
+    let mut w1 = World::<2>::standard_world(quick_config(), 7);
+    let mut w2 = World::<2>::standard_world(quick_config(), 8);
+
+    w1.step(1);
+    w2.step(1);
+    assert_ne!(trajectory(&w1), trajectory(&w2));
+}
+
+#[test]
+fn batched_inference_matches_per_being() {
+    let mut batched = World::<2>::standard_world(quick_config(), 11);
+    let mut per_being = World::<2>::standard_world(quick_config(), 11);
+    per_being.set_batch_inference(false);
+
+    for _ in 0..200 {
+        batched.step(1);
+        per_being.step(1);
+        assert_eq!(trajectory(&batched), trajectory(&per_being));
+    }
+    assert!(batched.generation() > 0);
+}
+
+#[test]
+fn parallel_collisions_match_serial() {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build()
+        .unwrap();
+    let mut parallel = World::<2>::standard_world(quick_config(), 13);
+    let mut serial = World::<2>::standard_world(quick_config(), 13);
+    serial.set_parallel_collisions(false);
+
+    for _ in 0..200 {
+        pool.install(|| parallel.step(1));
+        serial.step(1);
+        assert_eq!(trajectory(&parallel), trajectory(&serial));
+    }
+    assert!(parallel.generation() > 0);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{quick_config, trajectory};
+use neuralang::{
+    islands::{run_islands, Migration},
+    World,
+};
+
+fn islands(seed: u64) -> Vec<World<2>> {
+    (0..2)
+        .map(|i| World::standard_world(quick_config(), seed + i))
+        .collect()
+}
+
+#[test]
+fn island_runs_repeat_exactly() {
+    let migration = Migration {
+        every: 1,
+        migrants: 2,
+    };
+    let (mut a, mut b) = (islands(21), islands(21));
+    run_islands(&mut a, 2, migration, vec![]);
+    run_islands(&mut b, 2, migration, vec![]);
+
+    for (wa, wb) in a.iter().zip(&b) {
+        assert_eq!(wa.generation(), 2);
+        assert_eq!(trajectory(wa), trajectory(wb));
+    }
+}
+
+#[test]
+fn migrants_join_the_breeding_pool() {
+    let mut from = World::<2>::standard_world(quick_config(), 23);
+    while from.step(1).is_none() {}
+    let mut to = World::<2>::standard_world(quick_config(), 24);
+    while to.step(1).is_none() {}
+
+    let natives = to.last_survivors().len();
+    let migrants = from.top_survivors(2);
+    assert_eq!(migrants.len(), 2.min(from.last_survivors().len()));
+    let n_migrants = migrants.len();
+
+    to.immigrate(migrants);
+    assert_eq!(to.last_survivors().len(), natives + n_migrants);
+    assert_eq!(to.age(), 0);
+    assert_eq!(
+        to.beings().count(),
+        quick_config().b_start_count.max(natives + n_migrants)
+    );
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::quick_config;
+use neuralang::{
+    config::SpeechChannel,
+    events::{EventKind, EventType},
+    stats::GenerationStats,
+    world::utterance,
+    SimConfig, World, WorldRng, B_OUTPUT_LEN, SPEECHLET_LEN,
+};
+use rand::SeedableRng;
+
+#[test]
+fn generation_stats_add_up() {
+    let cfg = quick_config();
+    let mut w = World::<2>::standard_world(cfg.clone(), 17);
+    let summary = loop {
+        if let Some(summary) = w.step(1) {
+            break summary;
+        }
+    };
+
+    // no being is born mid-generation, so everyone missing has died
+    assert_eq!(summary.stats.deaths, cfg.b_start_count - summary.survivors);
+    assert!(summary.mean_energy <= summary.max_energy);
+    assert_eq!(*w.stats(), GenerationStats::default());
+}
+
+#[test]
+fn events_are_recorded_only_when_wanted() {
+    let mut w = World::<2>::standard_world(quick_config(), 19);
+    for _ in 0..20 {
+        w.step(1);
+    }
+    assert!(w.take_events().is_empty());
+
+    w.record_events(&[EventType::Eat, EventType::Speak]);
+    for _ in 0..20 {
+        w.step(1);
+    }
+    let events = w.take_events();
+    assert!(!events.is_empty());
+    assert!(events
+        .iter()
+        .all(|e| matches!(e.kind, EventKind::Eat { .. } | EventKind::Speak { .. })));
+
+    // pos is always the being's, the food it ate is within reach of it
+    let reach = quick_config().b_radius + quick_config().f_radius;
+    assert!(events.iter().any(|e| matches!(e.kind, EventKind::Eat { .. })));
+    for e in &events {
+        if let EventKind::Eat { at, .. } = e.kind {
+            assert!(e.pos.distance(at) <= reach, "{:?}", e);
+        }
+    }
+}
+
+#[test]
+fn discrete_speech_is_one_hot_and_counted() {
+    let mut rng = WorldRng::seed_from_u64(0);
+    let mut output = [0.; B_OUTPUT_LEN];
+    output[3] = 1.;
+    output[4 + 2] = 0.9;
+    output[4 + 6] = 0.95;
+
+    let (continuous, token) = utterance(&output, &quick_config(), &mut rng);
+    assert_eq!(token, None);
+    assert_eq!(continuous[..], output[3..3 + SPEECHLET_LEN]);
+
+    let argmax = |s_vocab_size| SimConfig {
+        s_channel: SpeechChannel::Argmax,
+        s_vocab_size,
+        ..quick_config()
+    };
+    assert_eq!(utterance(&output, &argmax(8), &mut rng).1, Some(6));
+    let (speechlet, token) = utterance(&output, &argmax(4), &mut rng);
+    assert_eq!(token, Some(2));
+    assert_eq!(speechlet.iter().sum::<f32>(), 1.);
+    assert_eq!(speechlet[2], 1.);
+
+    let gumbel = SimConfig {
+        s_channel: SpeechChannel::Gumbel,
+        s_vocab_size: 4,
+        ..quick_config()
+    };
+    let mut drawn = [0; 4];
+    for _ in 0..400 {
+        drawn[utterance(&output, &gumbel, &mut rng).1.unwrap()] += 1;
+    }
+    assert!(drawn.iter().all(|n| *n > 0) && drawn[2] == *drawn.iter().max().unwrap());
+
+    // every word spoken in a world is a counted token from the vocabulary
+    let mut w = World::<2>::standard_world(argmax(3), 19);
+    w.record_events(&[EventType::Speak]);
+    for _ in 0..40 {
+        w.step(1);
+    }
+    let stats = *w.stats();
+    assert!(stats.speechlets_emitted > 0);
+    assert_eq!(
+        stats.tokens_spoken.iter().sum::<usize>(),
+        stats.speechlets_emitted
+    );
+    assert!(stats.tokens_spoken[3..].iter().all(|n| *n == 0));
+    assert!(stats.vocab_used() <= 3);
+    assert!(stats.vocab_entropy() <= 3f32.log2() + 1e-6);
+    for e in w.take_events() {
+        let EventKind::Speak { speechlet, token } = e.kind else {
+            unreachable!()
+        };
+        let t = token.unwrap();
+        assert!(t < 3 && speechlet[t] == 1. && speechlet.iter().sum::<f32>() == 1.);
+    }
+}
@@ -0,0 +1,406 @@
+mod common;
+
+use common::{quick_config, trajectory};
+use glam::Vec2;
+use neuralang::{
+    baselines::GreedyForager,
+    brain::{Brain, InputWidths, Observation},
+    config::{Acoustics, Occlusion},
+    events::{EventKind, EventType},
+    retina_nn::RetinaModel,
+    world::{
+        egocentric, in_view, is_occluded, ray_hit, sound_gain, speechlet_row, walls_between, Being,
+    },
+    SimConfig, World, WorldRng, BACKEND, B_OUTPUT_LEN, GENOME_LEN, RETINA_CHANNELS, SPEECHLET_LEN,
+};
+use serde::{Deserialize, Serialize};
+
+// stands still and keeps everything it was last shown, to look at what a world observes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Recorder {
+    seen: Vec<f32>,
+}
+
+impl Brain for Recorder {
+    type State = Recorder;
+
+    fn random(_rng: &mut WorldRng, _widths: InputWidths) -> Self {
+        Recorder::default()
+    }
+
+    fn act(&mut self, obs: &Observation) -> [f32; B_OUTPUT_LEN] {
+        let rows = obs
+            .beings
+            .iter()
+            .chain(obs.food_obstructs)
+            .chain(obs.speechlets);
+        self.seen = rows
+            .flatten()
+            .chain(&obs.own)
+            .chain(obs.retina)
+            .copied()
+            .collect();
+        let mut out = [-1.; B_OUTPUT_LEN];
+        out[..2].fill(0.);
+        out
+    }
+
+    fn reset(&mut self) {}
+
+    fn crossover(self, _other: Self, _weight: f32) -> Self {
+        self
+    }
+
+    fn mutate(self, _rate: f32, _rng: &mut WorldRng) -> Self {
+        self
+    }
+
+    fn to_state(&self) -> Self::State {
+        self.clone()
+    }
+
+    fn from_state(state: &Self::State, _widths: InputWidths) -> Result<Self, String> {
+        Ok(state.clone())
+    }
+}
+
+// two beings and a food overlapping across diagonal cells, nothing else in the world
+fn huddle(config: SimConfig) -> World<2, Recorder> {
+    let mut w = World::new(config, 0);
+    let r = w.config().b_radius;
+    for pos in [Vec2::new(10.5, 10.5), Vec2::new(13.6, 13.6)] {
+        w.add_being(r, pos, 0., 10., [0.; GENOME_LEN], 0, Recorder::default());
+    }
+    w.add_food(Vec2::new(10.5, 14.), 1., false);
+    w
+}
+
+#[test]
+fn collisions_do_not_depend_on_sight() {
+    // b_fov 1 is the shortest sight there is, shorter than contact_reach
+    for b_fov in [1, 10] {
+        let mut w = huddle(SimConfig {
+            b_fov,
+            ..quick_config()
+        });
+        w.check_collisions(1);
+        assert_eq!(w.stats().being_collisions, 2, "b_fov {}", b_fov);
+        assert_eq!(w.stats().food_eaten, 1, "b_fov {}", b_fov);
+
+        w.perform_being_outputs();
+        for (_, brain) in w.beings() {
+            // the other being and the food are in sight, not only the being itself
+            assert!(brain.seen.len() > 5, "b_fov {}", b_fov);
+            assert!(brain.seen.iter().all(|v| v.is_finite()), "b_fov {}", b_fov);
+        }
+    }
+}
+
+#[test]
+fn contact_reach_covers_the_largest_overlap() {
+    let config = quick_config();
+    assert_eq!(config.contact_reach(), 2);
+
+    let wide = SimConfig {
+        o_radius: 7.,
+        ..quick_config()
+    };
+    assert_eq!(wide.contact_reach(), 4);
+}
+
+// a lone being in the middle of the world, facing +x
+fn looker(config: SimConfig) -> World<2, GreedyForager> {
+    let mut w = World::new(config, 0);
+    let (r, mid) = (w.config().b_radius, Vec2::splat(75.));
+    w.add_being(r, mid, 0., 10., [0.; GENOME_LEN], 0, GreedyForager);
+    w
+}
+
+fn sees(w: &World<2, GreedyForager>, dx: f32, dy: f32) -> bool {
+    let b: &Being = w.beings().next().unwrap().0;
+    in_view(b, b.pos() + Vec2::new(dx, dy), w.config())
+}
+
+#[test]
+fn vision_cone_follows_facing() {
+    let cone = looker(SimConfig {
+        b_view_half_angle_degrees: 60.,
+        ..quick_config()
+    });
+    assert!(sees(&cone, 10., 0.));
+    assert!(sees(&cone, 10., 10.));
+    assert!(!sees(&cone, 0., 10.));
+    assert!(!sees(&cone, -10., 0.));
+
+    let blind = looker(SimConfig {
+        b_blind_half_angle_degrees: 30.,
+        ..quick_config()
+    });
+    assert!(sees(&blind, 0., 10.));
+    assert!(sees(&blind, -10., 10.));
+    assert!(!sees(&blind, -10., 1.));
+
+    let short = looker(SimConfig {
+        b_view_range: 2.,
+        ..quick_config()
+    });
+    assert!(sees(&short, 5., 0.));
+    assert!(!sees(&short, 7., 0.));
+}
+
+#[test]
+fn obstructs_hide_what_is_behind_them() {
+    let cfg = quick_config();
+    let (from, wall) = (Vec2::new(50., 50.), Vec2::new(60., 50.));
+    assert!(is_occluded(from, Vec2::new(70., 50.), &[wall], &cfg));
+    assert!(is_occluded(from, Vec2::new(70., 51.), &[wall], &cfg));
+    assert!(!is_occluded(from, Vec2::new(70., 60.), &[wall], &cfg));
+    assert!(!is_occluded(from, Vec2::new(55., 50.), &[wall], &cfg));
+    assert!(!is_occluded(from, wall, &[wall], &cfg));
+}
+
+#[test]
+fn occlusion_modes_shape_the_observed_rows() {
+    for (b_occlusion, widths) in [(Occlusion::Off, (13, 5)), (Occlusion::Flag, (14, 6))] {
+        let config = SimConfig {
+            b_occlusion,
+            ..quick_config()
+        };
+        let w = config.input_widths();
+        assert_eq!((w.being, w.food_obstruct), widths);
+    }
+
+    // flagged or excluded, worlds with walls in them keep running
+    for b_occlusion in [Occlusion::Exclude, Occlusion::Flag] {
+        let mut w = World::<2>::standard_world(
+            SimConfig {
+                b_occlusion,
+                ..quick_config()
+            },
+            3,
+        );
+        for _ in 0..60 {
+            w.step(1);
+        }
+        assert!(w.obstructs().count() > 0);
+    }
+}
+
+#[test]
+fn rays_stop_at_the_nearest_edge() {
+    let (from, right) = (Vec2::new(50., 50.), Vec2::X);
+    assert_eq!(ray_hit(from, right, Vec2::new(60., 50.), 2.), Some(8.));
+    assert_eq!(ray_hit(from, right, Vec2::new(60., 53.), 2.), None);
+    assert_eq!(ray_hit(from, right, Vec2::new(40., 50.), 2.), None);
+    assert_eq!(ray_hit(from, right, Vec2::new(51., 50.), 2.), Some(0.));
+}
+
+#[test]
+fn retina_brains_need_rays_and_repeat_exactly() {
+    let config = SimConfig {
+        b_retina_rays: 8,
+        ..quick_config()
+    };
+    assert!(RetinaModel::<BACKEND>::check_widths(quick_config().input_widths()).is_err());
+    assert_eq!(config.input_widths().retina, 8 * RETINA_CHANNELS);
+
+    let mut a = World::<2, RetinaModel<BACKEND>>::standard_world(config.clone(), 4);
+    let mut b = World::<2, RetinaModel<BACKEND>>::standard_world(config, 4);
+    for _ in 0..60 {
+        a.step(1);
+        b.step(1);
+        assert_eq!(trajectory(&a), trajectory(&b));
+    }
+}
+
+#[test]
+fn egocentric_rows_follow_the_heading() {
+    let w = looker(SimConfig {
+        obs_version: 2,
+        ..quick_config()
+    });
+    let b: &Being = w.beings().next().unwrap().0;
+    let fov_px = w.config().b_fov_px();
+
+    // straight ahead, then to the left, coming closer
+    let ahead = egocentric(b, b.pos() + Vec2::new(15., 0.), Vec2::ZERO, w.config());
+    assert_eq!(ahead, [15. / fov_px, 0., 1., 0., 0.]);
+    let left = egocentric(
+        b,
+        b.pos() + Vec2::new(0., 15.),
+        Vec2::new(0., -1.),
+        w.config(),
+    );
+    assert!((left[1] - 1.).abs() < 1e-6 && left[2].abs() < 1e-6);
+    assert!(left[3].abs() < 1e-6 && (left[4] + 1.).abs() < 1e-6);
+
+    let widths = w.config().input_widths();
+    assert_eq!((widths.being, widths.food_obstruct), (16, 8));
+}
+
+#[test]
+fn speechlets_can_tell_where_they_came_from() {
+    let sound = [0.5; SPEECHLET_LEN];
+    let mut w = looker(quick_config());
+    w.add_speechlet(sound, Vec2::new(75., 77.), 7, 0, [0.; GENOME_LEN]);
+    let (b, s) = (w.beings().next().unwrap().0, w.speechlets().next().unwrap());
+    assert_eq!(speechlet_row(b, s, 1., w.config()), sound.to_vec());
+
+    let mut w = looker(SimConfig {
+        s_spatial_features: true,
+        ..quick_config()
+    });
+    w.add_speechlet(sound, Vec2::new(75., 75.5), 7, 0, [0.; GENOME_LEN]);
+    let (b, s) = (w.beings().next().unwrap().0, w.speechlets().next().unwrap());
+    let row = speechlet_row(b, s, 1., w.config());
+    assert_eq!(row.len(), w.config().input_widths().speechlet);
+    let [sin, cos, loudness, age] = row[SPEECHLET_LEN..] else {
+        unreachable!()
+    };
+    // to the left, a third of the way out of a fresh speechlet
+    assert!((sin - 1.).abs() < 1e-6 && cos.abs() < 1e-6);
+    assert!((loudness - 2. / 3.).abs() < 1e-6);
+    assert_eq!(age, 1.);
+}
+
+#[test]
+fn speechlets_remember_who_spoke_them() {
+    let sound = [0.5; SPEECHLET_LEN];
+    let heard = |config: SimConfig| {
+        let mut w = looker(config);
+        let id = w.beings().next().unwrap().0.id();
+        w.add_speechlet(sound, Vec2::splat(75.), id, 0, [0.; GENOME_LEN]);
+        w.add_speechlet(sound, Vec2::splat(76.), id + 1, 0, [0.; GENOME_LEN]);
+        w.record_events(&[EventType::Hear]);
+        w.check_collisions(1);
+        let speakers: Vec<usize> = w
+            .take_events()
+            .into_iter()
+            .map(|e| match e.kind {
+                EventKind::Hear { speaker, .. } => speaker - id,
+                _ => unreachable!(),
+            })
+            .collect();
+        speakers
+    };
+    let mut own_and_other = heard(quick_config());
+    own_and_other.sort();
+    assert_eq!(own_and_other, [0, 1]);
+    let muted = heard(SimConfig {
+        s_self_hearing: false,
+        ..quick_config()
+    });
+    assert_eq!(muted, [1]);
+
+    let config = SimConfig {
+        s_speaker_features: true,
+        ..quick_config()
+    };
+    let mut w = looker(config);
+    let id = w.beings().next().unwrap().0.id();
+    w.add_speechlet(sound, Vec2::splat(75.), id, 0, [0.; GENOME_LEN]);
+    w.add_speechlet(sound, Vec2::splat(75.), id + 1, 0, [0.; GENOME_LEN]);
+    w.add_speechlet(sound, Vec2::splat(76.), id + 1, 0, [0.; GENOME_LEN]);
+    let b = w.beings().next().unwrap().0;
+    let rows: Vec<Vec<f32>> = w
+        .speechlets()
+        .map(|s| speechlet_row(b, s, 1., w.config()))
+        .collect();
+    assert!(rows
+        .iter()
+        .all(|r| r.len() == w.config().input_widths().speechlet));
+    let codes: Vec<&[f32]> = rows.iter().map(|r| &r[SPEECHLET_LEN..]).collect();
+    // one speaker sounds the same wherever it speaks, the hearer knows its own voice
+    assert_eq!(codes[0][0], 1.);
+    assert_eq!(codes[1][0], 0.);
+    assert_eq!(codes[1], codes[2]);
+    assert_ne!(codes[0][1..], codes[1][1..]);
+}
+
+#[test]
+fn hearers_tell_kin_from_strangers() {
+    // a founder's children are of its clan, whichever parent they took after
+    let mut w = World::<2>::standard_world(quick_config(), 17);
+    let founders: Vec<usize> = w.beings().map(|(b, _)| b.clan()).collect();
+    assert_eq!(
+        founders,
+        (0..quick_config().b_start_count).collect::<Vec<_>>()
+    );
+    let summary = loop {
+        if let Some(summary) = w.step(1) {
+            break summary;
+        }
+    };
+    let mut clans: Vec<usize> = w.beings().map(|(b, _)| b.clan()).collect();
+    assert_eq!(clans.len(), quick_config().b_start_count);
+    clans.sort();
+    clans.dedup();
+    assert!(clans.len() <= summary.survivors && clans.iter().all(|c| founders.contains(c)));
+
+    // the same sound from two speakers, one of the hearer's clan
+    let sound = [0.5; SPEECHLET_LEN];
+    let mut w = looker(SimConfig {
+        s_speaker_features: true,
+        ..quick_config()
+    });
+    let b_clan = w.beings().next().unwrap().0.clan();
+    w.add_speechlet(sound, Vec2::splat(76.), 1, b_clan, [0.; GENOME_LEN]);
+    w.add_speechlet(sound, Vec2::splat(76.), 1, b_clan + 1, [0.; GENOME_LEN]);
+    let b = w.beings().next().unwrap().0;
+    let rows: Vec<Vec<f32>> = w
+        .speechlets()
+        .map(|s| speechlet_row(b, s, 1., w.config()))
+        .collect();
+    let kin = SPEECHLET_LEN + 3;
+    assert_eq!((rows[0][kin], rows[1][kin]), (1., 0.));
+    assert_eq!(rows[0][..kin], rows[1][..kin]);
+    assert_eq!(rows[0].len(), w.config().input_widths().speechlet);
+}
+
+#[test]
+fn walls_muffle_or_stop_sound() {
+    let (from, to) = (Vec2::new(50., 50.), Vec2::new(70., 50.));
+    let walls = [
+        Vec2::new(55., 50.),
+        Vec2::new(60., 51.),
+        Vec2::new(65., 60.),
+    ];
+    let cfg = quick_config();
+    assert_eq!(walls_between(from, to, &walls, &cfg), 2);
+    assert_eq!(sound_gain(from, to, &walls, &cfg), 1.);
+
+    let damped = SimConfig {
+        s_acoustics: Acoustics::Damp,
+        s_wall_damping: 0.5,
+        ..quick_config()
+    };
+    assert_eq!(sound_gain(from, to, &walls, &damped), 0.25);
+    assert_eq!(sound_gain(from, to, &walls[2..], &damped), 1.);
+    let blocked = SimConfig {
+        s_acoustics: Acoustics::Block,
+        ..quick_config()
+    };
+    assert_eq!(sound_gain(from, to, &walls, &blocked), 0.);
+
+    // a being with a wall between it and a speechlet it overlaps
+    let heard = |s_acoustics| {
+        let mut w = looker(SimConfig {
+            s_acoustics,
+            ..quick_config()
+        });
+        w.add_obstruct(Vec2::new(77., 75.));
+        w.add_speechlet(
+            [0.5; SPEECHLET_LEN],
+            Vec2::new(79., 75.),
+            99,
+            0,
+            [0.; GENOME_LEN],
+        );
+        w.record_events(&[EventType::Hear]);
+        w.check_collisions(1);
+        w.take_events().len()
+    };
+    assert_eq!(heard(Acoustics::Off), 1);
+    assert_eq!(heard(Acoustics::Damp), 1);
+    assert_eq!(heard(Acoustics::Block), 0);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::fs;
+
+use common::{quick_config, trajectory};
+use neuralang::{
+    being_nn::SumFxModel,
+    brain::BrainFile,
+    checkpoint::{latest_checkpoint, CheckpointPolicy},
+    config::Occlusion,
+    SimConfig, World, BACKEND, DEVICE,
+};
+
+#[test]
+fn snapshot_resumes_exactly() {
+    let path = std::env::temp_dir().join(format!("samsarsa-snapshot-{}.json", std::process::id()));
+
+    // mid-generation, after deaths have left holes in the slotmaps
+    let mut w1 = World::<2>::standard_world(quick_config(), 11);
+    for _ in 0..130 {
+        w1.step(1);
+    }
+    w1.save_snapshot(&path).unwrap();
+    let mut w2 = World::<2>::load_snapshot(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(trajectory(&w1), trajectory(&w2));
+    for _ in 0..150 {
+        w1.step(1);
+        w2.step(1);
+        assert_eq!(trajectory(&w1), trajectory(&w2));
+    }
+}
+
+#[test]
+fn brain_file_roundtrips() {
+    let path = std::env::temp_dir().join(format!("samsarsa-brain-{}.json", std::process::id()));
+
+    let mut w = World::<2>::standard_world(quick_config(), 5);
+    for _ in 0..20 {
+        w.step(1);
+    }
+    let (b, m) = w.champion().unwrap();
+    BrainFile::new(m, w.seed(), w.generation(), b.id())
+        .save(&path)
+        .unwrap();
+    let loaded = BrainFile::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let mut original = m.clone();
+    original.reset_state(&DEVICE);
+    let restored: SumFxModel<BACKEND> = loaded.to_model(quick_config().input_widths()).unwrap();
+    assert_eq!(
+        serde_json::to_string(&original.to_state()).unwrap(),
+        serde_json::to_string(&restored.to_state()).unwrap()
+    );
+
+    // a world whose rows are wider can not take it
+    let flagged = SimConfig {
+        b_occlusion: Occlusion::Flag,
+        ..quick_config()
+    };
+    let wide = flagged.input_widths();
+    assert!(loaded.to_model::<SumFxModel<BACKEND>>(wide).is_err());
+
+    // seeding places the brain itself first
+    let seeded = World::<2>::seeded_world(quick_config(), 5, &[restored]);
+    let (_, first) = seeded.beings().next().unwrap();
+    assert_eq!(
+        serde_json::to_string(&first.to_state()).unwrap(),
+        serde_json::to_string(&original.to_state()).unwrap()
+    );
+}
+
+#[test]
+fn checkpoints_keep_newest_and_resume() {
+    let dir = std::env::temp_dir().join(format!("samsarsa-checkpoints-{}", std::process::id()));
+    let policy = CheckpointPolicy {
+        dir: dir.clone(),
+        every: 1,
+        keep: 2,
+    };
+
+    let mut w = World::<2>::standard_world(quick_config(), 13);
+    while w.generation() < 3 {
+        if w.step(1).is_some() {
+            policy.write(&w).unwrap();
+        }
+    }
+    let mut kept: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    kept.sort();
+    assert_eq!(kept, ["checkpoint-000002.json", "checkpoint-000003.json"]);
+
+    let resumed = World::<2>::load_checkpoint(&latest_checkpoint(&dir).unwrap()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(resumed.generation(), w.generation());
+    assert_eq!(resumed.max_food(), w.max_food());
+    assert_eq!(resumed.last_survivors().len(), w.last_survivors().len());
+    assert_eq!(resumed.beings().count(), w.beings().count());
+}